@@ -1,4 +1,4 @@
-use clap::{ Parser, Subcommand, ValueHint };
+use clap::{ Parser, Subcommand, ValueEnum, ValueHint };
 use clap_complete::Shell;
 
 // cargo run -- --generate=zsh
@@ -21,9 +21,22 @@ pub struct Cli {
     #[clap(name = "verbose", long, short, parse(from_flag))]
     pub verbose: bool,
 
+    /// Print the commands instead of running them, and do not save anything
     #[clap(name = "dry-run", long, short, parse(from_flag))]
     pub dry_run: bool,
 
+    /// Only show commands used in the current project (git repository or directory)
+    #[clap(name = "here", long, global = true, parse(from_flag))]
+    pub here: bool,
+
+    /// Match the search pattern as a plain substring instead of fuzzy matching it
+    #[clap(name = "substring", long, global = true, parse(from_flag))]
+    pub substring: bool,
+
+    /// Print the selected command instead of running it, for shell widgets
+    #[clap(name = "print", long, global = true, parse(from_flag))]
+    pub print: bool,
+
     #[clap(long = "generate", value_enum)]
     pub generator: Option<Shell>,
 }
@@ -41,15 +54,136 @@ pub enum Commands {
     Get {
         #[clap(value_parser, value_hint = ValueHint::CommandName)]
         pattern: Option<String>,
+
+        /// Only commands with this tag. Can be repeated.
+        #[clap(name = "tag", long, short, value_parser)]
+        tags: Vec<String>,
     },
     Clear {},
 
     Delete {},
 
+    /// List previous executions
+    History {
+        /// Only show executions whose command contains this text
+        #[clap(value_parser)]
+        pattern: Option<String>,
+
+        /// Only show executions that ran in this directory or below it
+        #[clap(long, short, value_parser, value_hint = ValueHint::DirPath)]
+        dir: Option<String>,
+
+        /// Only show executions on or after this date (YYYY-MM-DD)
+        #[clap(long, value_parser)]
+        since: Option<String>,
+
+        /// Only show executions on or before this date (YYYY-MM-DD)
+        #[clap(long, value_parser)]
+        until: Option<String>,
+
+        /// Only show executions that did not exit successfully
+        #[clap(long, parse(from_flag))]
+        failed: bool,
+
+        /// Maximum number of executions to show
+        #[clap(long, short = 'n', value_parser, default_value_t = 50)]
+        limit: usize,
+    },
+
+    /// Print the code of a Ctrl-G widget that puts the selected command in the prompt
+    Init {
+        #[clap(value_enum)]
+        shell: ShellKind,
+    },
+
+    /// Save commands from elsewhere
+    Import {
+        #[clap(subcommand)]
+        source: ImportSource,
+    },
+
+    /// Suggest commands worth saving from the recent shell history
+    Suggest {
+        /// Defaults to the shell in $SHELL
+        #[clap(long, value_enum)]
+        shell: Option<ShellKind>,
+
+        /// Defaults to $HISTFILE or the shell's own history file
+        #[clap(long, value_parser, value_hint = ValueHint::FilePath)]
+        file: Option<String>,
+
+        /// How many of the latest history entries to look at
+        #[clap(long, value_parser, default_value_t = 1000)]
+        recent: usize,
+
+        /// Maximum number of suggestions
+        #[clap(long, short = 'n', value_parser, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Tag commands to group them
+    Tag {
+        #[clap(subcommand)]
+        action: TagAction,
+    },
+
     Debug {
         #[clap(value_parser, value_hint = ValueHint::CommandName)]
         pattern: Option<Shell>,
     },
     // Stash(Stash),
     // #[clap(external_subcommand)] External(Vec<OsString>),
-}
\ No newline at end of file
+}
+
+/// The shells with a widget for `cmd init` and a history `cmd import history` can read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ShellKind {
+    Zsh,
+    Bash,
+    Fish,
+}
+
+#[derive(Debug, Subcommand, Clone)]
+pub enum ImportSource {
+    /// Pick commands from the shell history, most used first
+    History {
+        /// Defaults to the shell in $SHELL
+        #[clap(long, value_enum)]
+        shell: Option<ShellKind>,
+
+        /// Defaults to $HISTFILE or the shell's own history file
+        #[clap(long, value_parser, value_hint = ValueHint::FilePath)]
+        file: Option<String>,
+
+        /// Maximum number of commands to offer
+        #[clap(long, short = 'n', value_parser, default_value_t = 200)]
+        limit: usize,
+    },
+}
+
+#[derive(Debug, Subcommand, Clone)]
+pub enum TagAction {
+    /// Add a tag to a command
+    Add {
+        #[clap(value_parser)]
+        tag: String,
+
+        /// Search for the command to tag
+        #[clap(value_parser, value_hint = ValueHint::CommandName)]
+        pattern: Option<String>,
+    },
+    /// Remove a tag from a command
+    Remove {
+        #[clap(value_parser)]
+        tag: String,
+
+        /// Search for the command to untag
+        #[clap(value_parser, value_hint = ValueHint::CommandName)]
+        pattern: Option<String>,
+    },
+    /// List the tags, or the commands with a tag
+    List {
+        #[clap(value_parser)]
+        tag: Option<String>,
+    },
+}
@@ -25,18 +25,44 @@ impl AddHandler {
         // let deps = &mut self.deps.as_ref().borrow_mut();
         let note = self.get_input().get_input(Some("Write your command".into()));
         print!("{}", note);
+        let description = self.get_input().get_optional_input(Some("Description (optional)".into()));
+        let tags = self.get_input()
+            .get_optional_input(Some("Tags, separated by commas (optional)".into()))
+            .map(|tags| parse_tags(&tags))
+            .unwrap_or_default();
         let os  = self.get_os();
 
        
 
         let controller = &mut self.deps.as_ref().borrow_mut().controller;
         
-        match execute {
-            true => {
-                os.execute_command(&note)?;
-                controller.new_command(note)
+        let outcome = match execute {
+            true => Some(os.execute_command(&note, &note)?),
+            false => None,
+        };
+        controller.new_command(note.clone())?;
+
+        // In a dry run the command was not added, so there is nothing to describe or tag
+        if (description.is_some() || !tags.is_empty()) && !controller.dry_run {
+            let record = controller
+                .find_command(&note)
+                .ok_or_else(|| CmdError::BaseError(format!("Could not find '{}' after adding it", note)))?;
+            controller.describe_command(record.clone(), description)?;
+            for tag in tags {
+                controller.add_tag(&record, &tag)?;
             }
-            false => { controller.new_command(note) }
+        }
+
+        match outcome {
+            Some(outcome) if !outcome.success() => Err(CmdError::ExitCodeError(outcome.exit_code())),
+            _ => Ok(()),
         }
     }
 }
+
+fn parse_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
@@ -5,8 +5,7 @@ use crate::{
     error::CmdError,
     models::{cmd_record::CmdRecord, self},
     log_debug,
-    log_info,
-    log_error, traits::inputable::Inputable,
+    traits::inputable::Inputable,
 };
 
 use super::cmd_get::GetHandler;
@@ -79,7 +78,7 @@ impl DeleteHandler {
     
         commands.extend(non_used_commands);
     
-        commands.sort_by(|cmd1, cmd2| cmd2.used_times.cmp(&cmd1.used_times));
+        mem.rank(&mut commands, None);
     
         let options = commands
             .iter()
@@ -87,8 +86,7 @@ impl DeleteHandler {
             .collect::<Vec<_>>();
     
         if options.is_empty() {
-            log::warn!("No command matched the pattern");
-            std::process::exit(1);
+            return Err(CmdError::NoMatchError);
         }
     
         return Ok((commands, options));
@@ -100,9 +98,9 @@ impl DeleteHandler {
     ) -> Result<(Vec<CmdRecord>, Vec<String>), CmdError> {
         let mem = &mut self.deps.as_ref().borrow_mut().controller;
     
-        let commands = self.get_commands_list(&mem.get_used_commands(parsed.clone()), |_x| true);
+        let commands = mem.get_recent_commands(parsed.clone(), None);
     
-        println!("Used commands: {:?}", commands);
+        log_debug!("Used commands: {:?}", commands);
     
         let options = commands
             .iter()
@@ -110,8 +108,7 @@ impl DeleteHandler {
             .collect::<Vec<_>>();
     
         if options.is_empty() {
-            log::warn!("No command matched the pattern");
-            std::process::exit(1);
+            return Err(CmdError::NoMatchError);
         }
     
         return Ok((commands, options));
@@ -128,16 +125,16 @@ impl DeleteHandler {
         let selected_cmd_index = match selection {
             Some(ind) => { ind }
             None => {
-                log_info!("No command was selected. Exiting...");
-                std::process::exit(1);
+                return Err(CmdError::NoSelectionError);
             }
         };
     
         let selected_cmd = match options.get(selected_cmd_index) {
             Some(res) => res,
             None => {
-                log_error!("Could not get option {} for options {:?}", selected_cmd_index, options);
-                std::process::exit(1);
+                return Err(
+                    CmdError::BaseError(format!("Could not get option {} for options {:?}", selected_cmd_index, options))
+                );
             }
         };
         let _parsed_cmd = String::from(selected_cmd);
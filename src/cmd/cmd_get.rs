@@ -1,39 +1,99 @@
-use std::{collections::HashSet, env, rc::Rc, cell::RefCell};
-use crate::{ *, error::CmdError, models::cmd_record::CmdRecord };
-use regex::Regex;
+use std::{collections::{ HashMap, HashSet }, env, io::{ self, Write }, rc::Rc, cell::RefCell};
+use crate::{
+    *,
+    error::CmdError,
+    models::{ cmd_record::{ CmdRecord, CmdSource }, history_record::{ HistoryRecord, timestamp_now } },
+    services::{
+        controller::parse_tag,
+        env_expander,
+        fuzzy::{ fuzzy_match, substring_positions },
+        location::Location,
+        template::{ self, Placeholder, Template },
+    },
+    traits::cmd_extension::{ PlaceholderContext, PlaceholderValue },
+};
+
+/// How much the full-text (bm25) relevance weighs against the fuzzy match score
+const FULL_TEXT_WEIGHT: f64 = 10.0;
+/// Last option when picking a value used before for a placeholder
+const NEW_VALUE_OPTION: &str = "New value...";
 
 pub struct GetHandler {
-    deps: Rc<RefCell<Deps>>
+    deps: Rc<RefCell<Deps>>,
+    /// Lines printed by each provider command, so that it only runs once per session
+    provider_output: RefCell<HashMap<String, Vec<String>>>,
+    /// Template, placeholder key and value of each filled placeholder, remembered once the
+    /// command succeeds
+    placeholder_values: RefCell<Vec<(String, String, String)>>,
+    /// Where `--print` writes the command, and nothing else
+    output: RefCell<Box<dyn Write>>,
 }
 
 impl GetHandler {
     pub fn new(deps: Rc<RefCell<Deps>>) -> Self {
         Self {
-            deps:deps
+            deps,
+            provider_output: RefCell::new(HashMap::new()),
+            placeholder_values: RefCell::new(Vec::new()),
+            output: RefCell::new(Box::new(io::stdout())),
         }
     } 
 
+    /// Where `--print` writes the command instead of stdout
+    pub fn with_output(mut self, output: Box<dyn Write>) -> Self {
+        self.output = RefCell::new(output);
+        self
+    }
+
     fn get_comand(&self) -> std::option::Option<Commands> {
         return self.deps.as_ref().borrow().args.command.clone();
     }
 
+    fn is_here(&self) -> bool {
+        return self.deps.as_ref().borrow().args.here;
+    }
+
+    fn is_substring(&self) -> bool {
+        return self.deps.as_ref().borrow().args.substring;
+    }
+
+    fn is_print(&self) -> bool {
+        return self.deps.as_ref().borrow().args.print;
+    }
+
+    /// The tags passed with `cmd get -t`
+    fn get_tags(&self) -> Result<Vec<String>, CmdError> {
+        match self.get_comand() {
+            Some(Commands::Get { tags, .. }) => tags.iter().map(|tag| parse_tag(tag)).collect(),
+            _ => Ok(Vec::new()),
+        }
+    }
+
     fn get_input(&self) -> Rc<dyn Inputable> {
         return Rc::clone(&self.deps.as_ref().borrow().input);
     }
 
-    fn get_commands(&self, pattern: String) -> Vec<CmdRecord> {
-        return self.deps.as_ref().borrow_mut().controller.get_commands(pattern)
+    fn get_commands(&self, pattern: String, tags: &[String]) -> Vec<CmdRecord> {
+        return self.deps.as_ref().borrow_mut().controller.get_tagged_commands(pattern, tags)
+    }
+
+    fn get_used_commands(&self, pattern: String, tags: &[String]) -> Vec<CmdRecord> {
+        return self.deps.as_ref().borrow_mut().controller.get_used_tagged_commands(pattern, tags)
     }
 
-    fn get_used_commands(&self, pattern: String) -> Vec<CmdRecord> {
-        return self.deps.as_ref().borrow_mut().controller.get_used_commands(pattern)
+    fn get_project_commands(&self, pattern: String, location: &Location, tags: &[String]) -> Vec<CmdRecord> {
+        return self.deps.as_ref().borrow_mut().controller.get_project_commands(pattern, location, tags)
     }
 
     fn add_used_command(&self, record: CmdRecord,
         alias: Option<String>) -> Result<(), error::CmdError> {
             return self.deps.as_ref().borrow_mut().controller.add_used_command(record, alias)
         }
-    
+
+    fn add_history(&self, entry: HistoryRecord) -> Result<(), error::CmdError> {
+        return self.deps.as_ref().borrow_mut().controller.add_history(entry)
+    }
+
 
     pub fn get_command(&mut self, pattern: &Option<String>) -> Result<(), CmdError> {
 
@@ -86,23 +146,87 @@ impl GetHandler {
     }
     
     fn get_matches(&mut self, parsed: String) -> Result<(Vec<CmdRecord>, Vec<String>), CmdError> {
+        let location = Location::current();
+        let tags = self.get_tags()?;
+        let fuzzy = !self.is_substring() && !parsed.trim().is_empty();
+
+        // Fuzzy matching happens here rather than in the storage, so start from every command
+        let query = match fuzzy {
+            true => String::new(),
+            false => parsed.clone(),
+        };
 
-        let mut commands = self.get_used_commands(parsed.clone()).clone();
-    
-        let set: HashSet<_> = commands
-            .clone()
-            .drain(..)
-            .map(|e| e.command)
-            .collect::<HashSet<_>>(); // dedup
-    
-        let non_used_commands = self.get_commands_list(
-            &self.get_commands(parsed.clone()),
-            |x| !set.contains(&x.command)
-        );
-    
-        commands.extend(non_used_commands);
-    
-        commands.sort_by(|cmd1, cmd2| cmd2.used_times.cmp(&cmd1.used_times));
+        let mut commands = match self.is_here() {
+            true => self.get_project_commands(query.clone(), &location, &tags),
+            false => {
+                let mut commands = self.get_used_commands(query.clone(), &tags).clone();
+
+                let set: HashSet<_> = commands
+                    .clone()
+                    .drain(..)
+                    .map(|e| e.command)
+                    .collect::<HashSet<_>>(); // dedup
+
+                let non_used_commands = self.get_commands_list(
+                    &self.get_commands(query.clone(), &tags),
+                    |x| !set.contains(&x.command)
+                );
+
+                commands.extend(non_used_commands);
+                commands
+            }
+        };
+
+        // The commands of the project files and the tasks of its build files are offered too
+        let offered = {
+            let deps = self.deps.as_ref().borrow();
+            let mut offered = deps.project.records();
+            offered.extend(deps.tasks.tasks());
+            offered
+        };
+        let lowercase_query = query.to_lowercase();
+        for record in offered {
+            let matches = record.command.to_lowercase().contains(&lowercase_query) &&
+                tags.iter().all(|tag| record.tags.contains(tag));
+            if !matches {
+                continue;
+            }
+            match commands.iter_mut().find(|cmd| cmd.command == record.command) {
+                // Stored once it was used, but the project file still describes it
+                Some(stored) if matches!(record.source, CmdSource::Project { .. }) && record.description.is_some() => {
+                    stored.description = record.description;
+                }
+                Some(_) => {}
+                None => commands.push(record),
+            }
+        }
+
+        let controller = &mut self.deps.as_ref().borrow_mut().controller;
+        match fuzzy {
+            true => {
+                // Multi-word searches also go through the full-text index, which finds words
+                // in any order and weighs them by how rare they are
+                let full_text = match parsed.split_whitespace().count() > 1 {
+                    true => controller.search_full_text(&parsed),
+                    false => HashMap::new(),
+                };
+
+                let mut matches = commands
+                    .drain(..)
+                    .filter_map(|cmd| {
+                        let fuzzy_score = fuzzy_match(&parsed, &cmd.command).map(|m| m.score as f64);
+                        let full_text_score = full_text.get(&cmd.id).map(|rank| rank * FULL_TEXT_WEIGHT);
+                        if fuzzy_score.is_none() && full_text_score.is_none() {
+                            return None;
+                        }
+                        Some((cmd, fuzzy_score.unwrap_or(0.0) + full_text_score.unwrap_or(0.0)))
+                    })
+                    .collect::<Vec<_>>();
+                controller.rank_by_relevance(&mut matches, Some(&location));
+                commands.extend(matches.into_iter().map(|(cmd, _)| cmd));
+            }
+            false => controller.rank(&mut commands, Some(&location)),
+        }
     
         let options = commands
             .iter()
@@ -110,8 +234,7 @@ impl GetHandler {
             .collect::<Vec<_>>();
     
         if options.is_empty() {
-            log::warn!("No command matched the pattern");
-            std::process::exit(1);
+            return Err(CmdError::NoMatchError);
         }
     
         return Ok((commands, options));
@@ -122,9 +245,16 @@ impl GetHandler {
         parsed: String
     ) -> Result<(Vec<CmdRecord>, Vec<String>), CmdError> {
     
-        let commands = self.get_commands_list(&self.get_used_commands(parsed.clone()), |_x| true);
+        let location = match self.is_here() {
+            true => Some(Location::current()),
+            false => None,
+        };
+        let commands = self.deps
+            .as_ref()
+            .borrow_mut()
+            .controller.get_recent_commands(parsed.clone(), location.as_ref());
     
-        println!("Used commands: {:?}", commands);
+        log_debug!("Used commands: {:?}", commands);
     
         let options = commands
             .iter()
@@ -132,8 +262,7 @@ impl GetHandler {
             .collect::<Vec<_>>();
     
         if options.is_empty() {
-            log::warn!("No command matched the pattern");
-            std::process::exit(1);
+            return Err(CmdError::NoMatchError);
         }
     
         return Ok((commands, options));
@@ -145,59 +274,99 @@ impl GetHandler {
         commands: Vec<CmdRecord>,
         parsed: String
     ) -> Result<(), CmdError> {
-        let selection = self.deps.borrow().input.select_option(&options, None);
+        let highlights = options
+            .iter()
+            .map(|option| self.get_highlights(&parsed, option))
+            .collect::<Vec<_>>();
+        // Descriptions are only shown, the options are what gets executed
+        let items = commands
+            .iter()
+            .map(|cmd| cmd.display())
+            .collect::<Vec<_>>();
+        let selection = self.deps.borrow().input.select_highlighted(&items, &highlights, None);
     
         let selected_cmd_index = match selection {
             Some(ind) => { ind }
             None => {
-                log_info!("No command was selected. Exiting...");
-                std::process::exit(1);
+                return Err(CmdError::NoSelectionError);
             }
         };
     
         let selected_cmd = match options.get(selected_cmd_index) {
             Some(res) => res,
             None => {
-                log_error!("Could not get option {} for options {:?}", selected_cmd_index, options);
-                std::process::exit(1);
+                return Err(
+                    CmdError::BaseError(format!("Could not get option {} for options {:?}", selected_cmd_index, options))
+                );
             }
         };
         let parsed_cmd = String::from(selected_cmd);
     
-        let selected_record = &mut self.deps.as_ref().borrow_mut().controller
-            .get_used_commands(parsed.clone())
-            .get(selected_cmd_index)
-            .unwrap_or_else(|| commands.get(selected_cmd_index).unwrap())
-            .to_owned();
+        let selected_record = &mut commands.get(selected_cmd_index).unwrap().to_owned();
     
-        // let git = CmdExtensionGit {};
+        let (final_cmd, final_count) = self.fill_placeholders(parsed_cmd, selected_record)?;
+        // Extensions can swap the command for another one, e.g. to add the right context
+        let final_cmd = self.deps
+            .as_ref()
+            .borrow()
+            .extensions.rewrite(&selected_record.command, &final_cmd)
+            .unwrap_or(final_cmd);
     
-        // TODO: Fix this
-        // if git.handle_if_match(&Some(selected_record.clone()), deps).is_some() {
-        //     return Ok(());
-        // }
-    
-        let (final_cmd, final_count) = self.fill_placeholders(
-            selected_cmd,
-            parsed_cmd,
-            selected_record
-        );
-    
-        log_debug!("Executing '{}'!", &final_cmd);
-    
-        let result = self.deps.borrow().os.execute_command(&final_cmd);
+        // The shell widget runs it, so it is only counted as used
+        if self.is_print() {
+            writeln!(self.output.borrow_mut(), "{}", final_cmd)?;
+            return self.record_use(selected_record, &final_cmd, final_count, true);
+        }
+
+        // Printed commands are left for the shell to expand
+        let expanded_cmd = match self.deps.as_ref().borrow().config.expand_env {
+            true => self.expand_env_vars(&final_cmd)?,
+            false => final_cmd.clone(),
+        };
+        let recorded_cmd = match self.deps.as_ref().borrow().config.record_expanded {
+            true => expanded_cmd.clone(),
+            false => final_cmd.clone(),
+        };
+
+        log_debug!("Executing '{}'!", &expanded_cmd);
+
+        let executed_at = timestamp_now();
+        let result = {
+            let deps = self.deps.as_ref().borrow();
+            // Extensions could have side effects, so dry runs leave execution to the OS service
+            let extension = match deps.args.dry_run {
+                true => None,
+                false => deps.extensions.execute(&selected_record.command, &expanded_cmd),
+            };
+            match extension {
+                Some(result) => result,
+                None => deps.os.execute_command(&expanded_cmd, &recorded_cmd),
+            }
+        };
     
         match result {
-            Ok(_) => {
-                log_info!("Finalized successfully");
-                let mut new_cmd = selected_record.to_owned().clone();
-                // new_cmd.update_command(&final_cmd);
-                let alias = if final_cmd.eq(&selected_record.command) { None } else { Some(final_cmd) };
-                new_cmd.used_times = final_count;
-                let used_command = &mut self.add_used_command(new_cmd.to_owned(), alias);
-                match used_command {
-                    Ok(_) => Ok(()),
-                    Err(err) => Err(err.to_owned().into()),
+            Ok(outcome) => {
+                log_info!("Finalized with {:?}", outcome);
+                self.record_use(selected_record, &final_cmd, final_count, outcome.success())?;
+                let location = Location::current();
+                self.add_history(HistoryRecord {
+                    // Offered commands that failed are not stored
+                    cmd_id: match selected_record.source {
+                        CmdSource::Saved => Some(selected_record.id),
+                        _ => None,
+                    },
+                    command: recorded_cmd,
+                    cwd: location.cwd,
+                    repo_root: location.repo_root,
+                    exit_code: outcome.code,
+                    duration_ms: outcome.duration.as_millis() as u64,
+                    executed_at,
+                    ..HistoryRecord::default()
+                })?;
+                // The command still counts as used, but cmd exits with its status
+                match outcome.success() {
+                    true => Ok(()),
+                    false => Err(CmdError::ExitCodeError(outcome.exit_code())),
                 }
             }
             Err(err) => {
@@ -207,55 +376,254 @@ impl GetHandler {
         }
     }
     
+    /// Counts the use of the command, and remembers the values of its placeholders if it
+    /// `succeeded`. Discovered tasks and project commands are stored the first time they succeed.
+    fn record_use(
+        &self,
+        record: &mut CmdRecord,
+        final_cmd: &str,
+        final_count: usize,
+        succeeded: bool
+    ) -> Result<(), CmdError> {
+        if record.source != CmdSource::Saved {
+            if !succeeded {
+                return Ok(());
+            }
+            self.save_offered(record)?;
+        }
+
+        if succeeded {
+            let controller = &mut self.deps.as_ref().borrow_mut().controller;
+            for (template, key, value) in self.placeholder_values.borrow_mut().drain(..) {
+                if let Err(err) = controller.add_placeholder_value(&template, &key, &value) {
+                    log_debug!("Could not remember the value of {}: {}", key, err);
+                }
+            }
+        }
+
+        let mut new_cmd = record.to_owned();
+        // Aliases are templates too, so keep literal braces in them escaped
+        let alias_cmd = template::escape(final_cmd);
+        let alias = if alias_cmd.eq(&record.command) { None } else { Some(alias_cmd) };
+        new_cmd.used_times = final_count;
+        self.add_used_command(new_cmd, alias)
+    }
+
+    /// Stores a discovered task or a project command, so that it is counted like any other.
+    /// Project commands keep their description and tags.
+    fn save_offered(&self, record: &mut CmdRecord) -> Result<(), CmdError> {
+        let controller = &mut self.deps.as_ref().borrow_mut().controller;
+        // Project commands can be stored already, when they are only offered for their tags
+        if controller.find_command(&record.command).is_none() {
+            controller.new_command(record.command.clone())?;
+        }
+        // Nothing is stored in dry runs
+        if let Some(stored) = controller.find_command(&record.command) {
+            controller.describe_command(stored.clone(), record.description.clone())?;
+            for tag in record.tags.iter().filter(|tag| !stored.tags.contains(tag)) {
+                controller.add_tag(&stored, tag)?;
+            }
+            record.id = stored.id;
+        }
+        record.source = CmdSource::Saved;
+        Ok(())
+    }
+
+    fn get_highlights(&self, parsed: &str, option: &str) -> Vec<usize> {
+        match self.is_substring() {
+            true => substring_positions(parsed, option),
+            false =>
+                fuzzy_match(parsed, option)
+                    .map(|m| m.positions)
+                    .unwrap_or_default(),
+        }
+    }
+
     pub fn fill_placeholders(
         &mut self,
-        selected_cmd: &String,
-        mut parsed_cmd: String,
+        parsed_cmd: String,
         selected_record: &mut CmdRecord
-    ) -> (String, usize) {
-       
-        parsed_cmd = self.parse_env_vars(selected_cmd, parsed_cmd);
-    
-        let (final_cmd, final_count) = match selected_cmd.contains("{}") {
+    ) -> Result<(String, usize), CmdError> {
+        let template = Template::parse(&parsed_cmd);
+        if !template.has_placeholders() {
+            return Ok((template.render(&[]), selected_record.used_times));
+        }
+
+        log_debug!("Fill placeholders");
+        // Prompts go to stderr too, so that `--print` only prints the command
+        eprintln!("Fill {} params...", template.placeholders.len());
+
+        let values = template.placeholders
+            .iter()
+            .enumerate()
+            .map(|(i, placeholder)| {
+                let context = PlaceholderContext {
+                    template: &selected_record.command,
+                    placeholder,
+                    before: template.text_before(i),
+                };
+                self.fill_placeholder(&context, selected_record.source, i)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((template.render(&values), 0))
+    }
+
+    /// Offers the values from its provider or an extension, or the ones used before for the
+    /// placeholder, or asks for a new one
+    fn fill_placeholder(
+        &self,
+        context: &PlaceholderContext,
+        source: CmdSource,
+        index: usize
+    ) -> Result<String, CmdError> {
+        let (template, placeholder) = (context.template, context.placeholder);
+        let (key, label) = match &placeholder.name {
+            Some(name) => (name.clone(), name.clone()),
+            None => (format!("#{}", index + 1), format!("param No.{}", index + 1)),
+        };
+
+        let previous = self.deps.as_ref().borrow_mut().controller.get_placeholder_values(template, &key);
+        let value = match placeholder.choices.is_empty() {
             true => {
-                log_debug!("Fill placeholders");
-    
-                let rest = selected_cmd.matches("{}");
-                let count = rest.count();
-    
-                println!("Fill {} params...", count);
-    
-                for i in 0..count {
-                    let note = self.get_input().get_input(Some(format!("Set param No.{}:", i + 1)));
-                    parsed_cmd = parsed_cmd.replacen("{}", &note, 1);
-                    // print!("{esc}c", esc = 27 as char);
-                    // print!("\r");
-                    // print!("{}", 8u8 as char);
+                let provided = match self.get_provider(template, source, placeholder) {
+                    Some(command) => {
+                        Some(self.run_provider(&command).into_iter().map(PlaceholderValue::new).collect())
+                    }
+                    None => self.deps.as_ref().borrow().extensions.placeholder_values(context),
+                };
+                let suggestions = match provided {
+                    Some(values) => prefer_previous(&values, &previous, |value| &value.value),
+                    None => previous.into_iter().map(PlaceholderValue::new).collect(),
+                };
+                match self.select_suggestion(&label, &suggestions) {
+                    Some(value) => value,
+                    None => {
+                        let prompt = format!("Set {}:", label);
+                        match &placeholder.default {
+                            Some(default) => self.get_input().get_input_with_default(Some(prompt), default.clone()),
+                            None => self.get_input().get_input(Some(prompt)),
+                        }
+                    }
                 }
-    
-                (parsed_cmd, 0)
             }
-            false => (parsed_cmd, selected_record.used_times),
+            false => self.select_choice(&label, &placeholder.choices, &previous)?,
         };
-        (final_cmd, final_count)
+
+        if !value.is_empty() {
+            self.placeholder_values.borrow_mut().push((template.to_string(), key, value.clone()));
+        }
+        Ok(value)
     }
-    
-    fn parse_env_vars(&self, selected_cmd:&String, mut parsed_cmd: String) -> String {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"([$]\w+)").unwrap();
+
+    /// Picks one of the allowed values, the ones used the most first
+    fn select_choice(
+        &self,
+        label: &str,
+        choices: &[String],
+        previous: &[String]
+    ) -> Result<String, CmdError> {
+        let options = prefer_previous(choices, previous, |choice| choice);
+        let selection = self.get_input().select_option(&options, Some(format!("Pick {}", label)));
+
+        selection
+            .and_then(|i| options.get(i))
+            .cloned()
+            .ok_or(CmdError::NoSelectionError)
+    }
+
+    /// Lets the user pick one of the suggested values, or `None` to type a new one
+    fn select_suggestion(&self, label: &str, suggestions: &[PlaceholderValue]) -> Option<String> {
+        if suggestions.is_empty() {
+            return None;
         }
-    
-        for cap in RE.captures_iter(selected_cmd) {
-            if let Ok(v) = env::var(&cap[1].replace("$","")) {
-                parsed_cmd = parsed_cmd.replace(&cap[1], &v);
+
+        let mut options = suggestions
+            .iter()
+            .map(|suggestion| suggestion.display())
+            .collect::<Vec<_>>();
+        options.push(NEW_VALUE_OPTION.to_string());
+        let selection = self.get_input().select_option(&options, Some(format!("Pick a value for {}", label)))?;
+
+        suggestions.get(selection).map(|suggestion| suggestion.value.clone())
+    }
+
+    /// The inline `$(command)` of the placeholder, or the provider registered for its name in
+    /// the project file the template comes from, or in the configuration. Inline providers of
+    /// commands from untrusted project files are not run.
+    fn get_provider(&self, template: &str, source: CmdSource, placeholder: &Placeholder) -> Option<String> {
+        let deps = self.deps.as_ref().borrow();
+        if let Some(provider) = &placeholder.provider {
+            // Stored project commands are still checked against their file
+            let trusted = match source {
+                CmdSource::Project { trusted } => trusted,
+                _ => deps.project.trusts(template),
+            };
+            match trusted {
+                true => return Some(provider.clone()),
+                false => log_warn!("Not running '{}', its project is not in trusted_projects", provider),
             }
         }
-        parsed_cmd
+
+        let name = placeholder.name.as_ref()?;
+        deps.project
+            .provider(template, name)
+            .or_else(|| deps.config.providers.get(name).cloned())
+    }
+
+    /// Non-empty lines printed by the provider. A failing provider offers nothing.
+    fn run_provider(&self, command: &str) -> Vec<String> {
+        if let Some(lines) = self.provider_output.borrow().get(command) {
+            return lines.clone();
+        }
+
+        let (os, timeout) = {
+            let deps = self.deps.as_ref().borrow();
+            (Rc::clone(&deps.os), deps.config.provider_timeout())
+        };
+        let lines = match os.capture_output(command, timeout) {
+            Ok(output) => {
+                let mut lines: Vec<String> = Vec::new();
+                for line in output.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+                    if !lines.iter().any(|l| l == line) {
+                        lines.push(line.to_string());
+                    }
+                }
+                lines
+            }
+            Err(err) => {
+                log_warn!("Could not get values from '{}': {}", command, err);
+                Vec::new()
+            }
+        };
+
+        self.provider_output.borrow_mut().insert(command.to_string(), lines.clone());
+        lines
+    }
+    
+    /// Expands the environment variables in the command, asking for the ones that are not set
+    fn expand_env_vars(&self, selected_cmd: &str) -> Result<String, CmdError> {
+        env_expander::expand(
+            selected_cmd,
+            |name| env::var(name).ok(),
+            |name| self.get_input().get_input(Some(format!("${} is not set, value to use:", name)))
+        )
     }
     
 }
 
 
+/// The values, with the ones used before first (most used first)
+fn prefer_previous<T: Clone>(values: &[T], previous: &[String], value: impl Fn(&T) -> &String) -> Vec<T> {
+    let mut ordered = previous
+        .iter()
+        .filter_map(|used| values.iter().find(|v| value(v) == used))
+        .cloned()
+        .collect::<Vec<_>>();
+    ordered.extend(values.iter().filter(|v| !previous.contains(value(v))).cloned());
+    ordered
+}
+
 // pub fn get_command(pattern: &Option<String>, deps: &mut Deps) -> Result<(), CmdError> {
 //     if deps.args.get_command.is_none() && deps.args.command.is_none() {
 //         let default_get_opts = vec!["Get recently used", "Get all"];
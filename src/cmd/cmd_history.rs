@@ -0,0 +1,122 @@
+use std::{ cell::RefCell, rc::Rc, path::Path };
+
+use crate::{
+    Deps,
+    error::CmdError,
+    models::history_record::HistoryRecord,
+    traits::cmd_service::HistoryFiltersBuilder,
+    log_warn,
+};
+
+pub struct HistoryHandler {
+    deps: Rc<RefCell<Deps>>,
+}
+
+pub struct HistoryQuery {
+    pub pattern: Option<String>,
+    pub dir: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub failed: bool,
+    pub limit: usize,
+}
+
+impl HistoryHandler {
+    pub fn new(deps: Rc<RefCell<Deps>>) -> Self {
+        Self {
+            deps
+        }
+    }
+
+    pub fn get_history(&self, query: HistoryQuery) -> Result<Vec<HistoryRecord>, CmdError> {
+        let mut builder = HistoryFiltersBuilder::default().failed(query.failed).limit(query.limit);
+
+        if let Some(pattern) = query.pattern {
+            builder = builder.command(pattern);
+        }
+        if let Some(dir) = query.dir {
+            // Stored directories are absolute, so resolve things like `.` or `../repo` first
+            let dir = Path::new(&dir)
+                .canonicalize()
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or(dir);
+            builder = builder.cwd(dir);
+        }
+        if let Some(since) = query.since {
+            builder = builder.since(parse_date(&since)?);
+        }
+        if let Some(until) = query.until {
+            builder = builder.until(parse_date(&until)?);
+        }
+
+        let filter = builder.build().map_err(|err| CmdError::BaseError(err.to_string()))?;
+
+        self.deps.as_ref().borrow_mut().controller.get_history(filter)
+    }
+
+    pub fn print_history(&self, query: HistoryQuery) -> Result<(), CmdError> {
+        let records = self.get_history(query)?;
+
+        if records.is_empty() {
+            log_warn!("No executions matched the filters");
+            return Ok(());
+        }
+
+        for record in records.iter().rev() {
+            let status = match record.exit_code {
+                Some(code) => code.to_string(),
+                None => "signal".to_string(),
+            };
+            println!(
+                "{}  [{}]  {:>8}  {}  {}",
+                record.executed_at_local,
+                status,
+                format_duration(record.duration_ms),
+                record.cwd,
+                record.command
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the date is a real `YYYY-MM-DD` day, as SQLite treats anything else as no date
+/// and nothing would match
+pub fn parse_date(date: &str) -> Result<String, CmdError> {
+    let invalid = || CmdError::InvalidDate(format!("'{}', expected YYYY-MM-DD", date));
+
+    let parts = date.split('-').collect::<Vec<_>>();
+    let lengths_match = parts
+        .iter()
+        .map(|part| part.len())
+        .eq([4, 2, 2]);
+    if !lengths_match || !parts.iter().all(|part| part.chars().all(|c| c.is_ascii_digit())) {
+        return Err(invalid());
+    }
+
+    let number = |part: &str| part.parse::<u32>().map_err(|_| invalid());
+    let (year, month, day) = (number(parts[0])?, number(parts[1])?, number(parts[2])?);
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => {
+            return Err(invalid());
+        }
+    };
+    if day == 0 || day > days {
+        return Err(invalid());
+    }
+
+    Ok(date.to_string())
+}
+
+fn format_duration(duration_ms: u64) -> String {
+    match duration_ms {
+        ms if ms < 1000 => format!("{}ms", ms),
+        ms if ms < 60_000 => format!("{:.1}s", (ms as f64) / 1000.0),
+        ms => format!("{}m{}s", ms / 60_000, (ms % 60_000) / 1000),
+    }
+}
@@ -0,0 +1,106 @@
+use std::{ cell::RefCell, cmp::Reverse, collections::HashMap, rc::Rc };
+
+use crate::{
+    Deps,
+    args::ShellKind,
+    error::CmdError,
+    models::cmd_record::CmdRecord,
+    services::{ shell_history::{ HistoryEntry, ShellHistory }, template },
+    traits::inputable::Inputable,
+    log_info,
+    log_warn,
+};
+
+pub struct ImportHandler {
+    deps: Rc<RefCell<Deps>>,
+}
+
+impl ImportHandler {
+    pub fn new(deps: Rc<RefCell<Deps>>) -> Self {
+        Self {
+            deps
+        }
+    }
+
+    fn get_input(&self) -> Rc<dyn Inputable> {
+        return Rc::clone(&self.deps.as_ref().borrow().input);
+    }
+
+    /// Offers the most used commands of the shell history that are not saved yet, and saves the
+    /// ones the user picks with their usage
+    pub fn import_history(
+        &self,
+        shell: Option<ShellKind>,
+        file: Option<String>,
+        limit: usize
+    ) -> Result<(), CmdError> {
+        let entries = shell.map_or(ShellHistory::Auto, ShellHistory::from).read(file.as_deref())?;
+
+        let ignore = self.deps.as_ref().borrow().config.import_ignore.clone();
+        let mut candidates = count_commands(entries, &ignore);
+        {
+            let controller = &mut self.deps.as_ref().borrow_mut().controller;
+            candidates.retain(|record| controller.find_command(&record.command).is_none());
+        }
+        candidates.truncate(limit);
+
+        if candidates.is_empty() {
+            log_warn!("There are no new commands in the shell history");
+            return Ok(());
+        }
+
+        let options = candidates
+            .iter()
+            .map(|record| format!("{}  ({} uses)", record.command, record.used_times))
+            .collect::<Vec<_>>();
+        let selection = self.get_input().select_multiple(&options, None);
+        let picked = candidates
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| selection.contains(i))
+            .map(|(_, record)| record)
+            .collect::<Vec<_>>();
+
+        let count = self.deps.as_ref().borrow_mut().controller.import_commands(picked)?;
+        log_info!("Imported {} commands", count);
+        Ok(())
+    }
+}
+
+/// One record per distinct command, with how many times it appears and when it was last used,
+/// most used first. Commands running an ignored program are left out.
+pub fn count_commands(entries: Vec<HistoryEntry>, ignore: &[String]) -> Vec<CmdRecord> {
+    let mut records: Vec<CmdRecord> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for entry in entries {
+        let line = entry.command.trim();
+        let program = line.split_whitespace().next().unwrap_or_default();
+        if line.is_empty() || ignore.iter().any(|ignored| ignored == program) {
+            continue;
+        }
+
+        // Saved commands are templates, and `stash@{0}` is not a placeholder
+        let command = template::escape(line);
+        match positions.get(&command) {
+            Some(position) => {
+                let record = &mut records[*position];
+                record.used_times += 1;
+                record.last_used = record.last_used.max(entry.timestamp);
+            }
+            None => {
+                positions.insert(command.clone(), records.len());
+                records.push(CmdRecord {
+                    command,
+                    used_times: 1,
+                    last_used: entry.timestamp,
+                    ..CmdRecord::default()
+                });
+            }
+        }
+    }
+
+    // Stable, so equally used commands keep the history order
+    records.sort_by_key(|record| Reverse(record.used_times));
+    records
+}
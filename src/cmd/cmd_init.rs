@@ -0,0 +1,23 @@
+use crate::args::ShellKind;
+
+/// Prints the code that sets up the shell widget, meant to be evaluated by the shell's startup
+/// file
+pub struct InitHandler {}
+
+impl InitHandler {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn script(&self, shell: ShellKind) -> &'static str {
+        match shell {
+            ShellKind::Zsh => include_str!("init/cmd.zsh"),
+            ShellKind::Bash => include_str!("init/cmd.bash"),
+            ShellKind::Fish => include_str!("init/cmd.fish"),
+        }
+    }
+
+    pub fn print_init(&self, shell: ShellKind) {
+        print!("{}", self.script(shell));
+    }
+}
@@ -0,0 +1,83 @@
+use std::{ cell::RefCell, collections::HashSet, rc::Rc };
+
+use crate::{
+    Deps,
+    args::ShellKind,
+    error::CmdError,
+    services::{ shell_history::ShellHistory, suggest::suggest },
+    traits::inputable::Inputable,
+    log_info,
+    log_warn,
+};
+
+pub struct SuggestHandler {
+    deps: Rc<RefCell<Deps>>,
+}
+
+pub struct SuggestQuery {
+    pub shell: Option<ShellKind>,
+    pub file: Option<String>,
+    /// How many of the latest history entries are scanned
+    pub recent: usize,
+    /// Maximum number of suggestions offered
+    pub limit: usize,
+}
+
+impl SuggestHandler {
+    pub fn new(deps: Rc<RefCell<Deps>>) -> Self {
+        Self {
+            deps
+        }
+    }
+
+    fn get_input(&self) -> Rc<dyn Inputable> {
+        return Rc::clone(&self.deps.as_ref().borrow().input);
+    }
+
+    /// Offers the commands of the recent shell history that are worth saving, and saves the
+    /// ones the user picks
+    pub fn suggest(&self, query: SuggestQuery) -> Result<(), CmdError> {
+        let entries = query.shell.map_or(ShellHistory::Auto, ShellHistory::from).read(query.file.as_deref())?;
+        let recent = &entries[entries.len().saturating_sub(query.recent)..];
+
+        let ignore = self.deps.as_ref().borrow().config.import_ignore.clone();
+        let saved = self.deps
+            .as_ref()
+            .borrow_mut()
+            .controller.get_commands(String::new())
+            .into_iter()
+            .map(|record| record.command)
+            .collect::<HashSet<_>>();
+
+        let mut suggestions = suggest(recent, &ignore, |command| saved.contains(command));
+        suggestions.truncate(query.limit);
+
+        if suggestions.is_empty() {
+            log_warn!("Nothing to suggest from the last {} commands", recent.len());
+            return Ok(());
+        }
+
+        let options = suggestions
+            .iter()
+            .map(|suggestion| {
+                match suggestion.examples.len() {
+                    1 => format!("{}  ({} uses)", suggestion.template, suggestion.uses),
+                    n => format!("{}  ({} uses of {} variants)", suggestion.template, suggestion.uses, n),
+                }
+            })
+            .collect::<Vec<_>>();
+        let selection = self.get_input().select_multiple(
+            &options,
+            Some("Pick the commands to save".to_string())
+        );
+
+        let controller = &mut self.deps.as_ref().borrow_mut().controller;
+        for (i, suggestion) in suggestions.into_iter().enumerate() {
+            if selection.contains(&i) {
+                controller.new_command(suggestion.template.clone())?;
+                log_info!("Saved '{}'", suggestion.template);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use std::{ cell::RefCell, rc::Rc };
+
+use crate::{
+    Deps,
+    error::CmdError,
+    models::cmd_record::CmdRecord,
+    services::controller::parse_tag,
+    traits::inputable::Inputable,
+    log_info,
+    log_warn,
+};
+
+pub struct TagHandler {
+    deps: Rc<RefCell<Deps>>,
+}
+
+impl TagHandler {
+    pub fn new(deps: Rc<RefCell<Deps>>) -> Self {
+        Self {
+            deps
+        }
+    }
+
+    fn get_input(&self) -> Rc<dyn Inputable> {
+        return Rc::clone(&self.deps.as_ref().borrow().input);
+    }
+
+    /// Lets the user pick one of the commands matching the pattern
+    fn select_command(
+        &self,
+        pattern: Option<String>,
+        tags: &[String],
+        prompt: &str
+    ) -> Option<CmdRecord> {
+        let pattern = pattern.unwrap_or_default();
+        let mut commands = self.deps
+            .as_ref()
+            .borrow_mut()
+            .controller.get_tagged_commands(pattern, tags);
+
+        if commands.is_empty() {
+            log_warn!("No command matched the pattern");
+            return None;
+        }
+
+        let options = commands
+            .iter()
+            .map(|cmd| cmd.display())
+            .collect::<Vec<_>>();
+        let selection = self.get_input().select_option(&options, Some(prompt.to_string()))?;
+
+        match selection < commands.len() {
+            true => Some(commands.remove(selection)),
+            false => None,
+        }
+    }
+
+    pub fn add_tag(&self, tag: &str, pattern: Option<String>) -> Result<(), CmdError> {
+        let tag = parse_tag(tag)?;
+        let record = match self.select_command(pattern, &[], "Pick a command to tag") {
+            Some(record) => record,
+            None => {
+                return Ok(());
+            }
+        };
+
+        self.deps.as_ref().borrow_mut().controller.add_tag(&record, &tag)?;
+        log_info!("Tagged '{}' with {}", record.command, tag);
+        Ok(())
+    }
+
+    pub fn remove_tag(&self, tag: &str, pattern: Option<String>) -> Result<(), CmdError> {
+        let tag = parse_tag(tag)?;
+        let record = match self.select_command(pattern, std::slice::from_ref(&tag), "Pick a command to untag") {
+            Some(record) => record,
+            None => {
+                return Ok(());
+            }
+        };
+
+        self.deps.as_ref().borrow_mut().controller.remove_tag(&record, &tag)?;
+        log_info!("Removed {} from '{}'", tag, record.command);
+        Ok(())
+    }
+
+    pub fn print_tags(&self, tag: Option<String>) -> Result<(), CmdError> {
+        let controller = &mut self.deps.as_ref().borrow_mut().controller;
+
+        match tag {
+            Some(tag) => {
+                let tag = parse_tag(&tag)?;
+                let commands = controller.get_tagged_commands(String::new(), std::slice::from_ref(&tag));
+                if commands.is_empty() {
+                    log_warn!("No command is tagged with {}", tag);
+                }
+                for cmd in commands {
+                    println!("{}", cmd.display());
+                }
+            }
+            None => {
+                let tags = controller.get_tags()?;
+                if tags.is_empty() {
+                    log_warn!("There are no tags yet");
+                }
+                for (name, count) in tags {
+                    println!("{} ({})", name, count);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,13 @@
+# cmd: Ctrl-G searches the saved commands for what is typed so far and puts the pick in the
+# prompt, to review, edit and run like any other command.
+# Add to ~/.bashrc:  eval "$(cmd init bash)"
+__cmd_widget() {
+  local selected
+  selected="$(command cmd get --print -- "${READLINE_LINE:0:$READLINE_POINT}" < /dev/tty)"
+  if [[ -n "$selected" ]]; then
+    READLINE_LINE="$selected"
+    READLINE_POINT=${#selected}
+  fi
+}
+bind -m emacs-standard -x '"\C-g": __cmd_widget'
+bind -m vi-insert -x '"\C-g": __cmd_widget'
@@ -0,0 +1,12 @@
+# cmd: Ctrl-G searches the saved commands for what is typed so far and puts the pick in the
+# prompt, to review, edit and run like any other command.
+# Add to ~/.config/fish/config.fish:  cmd init fish | source
+function __cmd_widget
+    set -l selected (command cmd get --print -- (commandline --cut-at-cursor) < /dev/tty | string collect)
+    if test -n "$selected"
+        commandline --replace -- $selected
+    end
+    commandline -f repaint
+end
+bind \cg __cmd_widget
+bind -M insert \cg __cmd_widget
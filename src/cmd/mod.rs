@@ -1,4 +1,9 @@
 pub mod cmd_add;
 pub mod cmd_clear;
 pub mod cmd_get;
-pub mod cmd_delete;
\ No newline at end of file
+pub mod cmd_delete;
+pub mod cmd_history;
+pub mod cmd_tag;
+pub mod cmd_init;
+pub mod cmd_import;
+pub mod cmd_suggest;
\ No newline at end of file
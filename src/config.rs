@@ -0,0 +1,120 @@
+use std::{ collections::HashMap, path::{ Path, PathBuf }, time::Duration };
+
+use serde::Deserialize;
+
+use crate::{
+    error::CmdError,
+    services::{ os_service::Execution, ranking::Ranking, shell_history::{ expand_home, ShellHistory } },
+};
+
+/// Settings read from `~/.cmd/config.toml`. Every key is optional.
+///
+/// ```toml
+/// ranking = "frecency" # or "used_times"
+/// provider_timeout = 5 # seconds
+/// expand_env = true # false leaves `$VAR` for the shell
+/// record_expanded = true # false keeps `$VAR` in the history instead of its value
+/// execution = "shell" # or "direct", to run programs without a shell
+/// shell = "/bin/zsh" # defaults to $SHELL
+/// shell_history = "auto" # "bash", "zsh", "zsh_extended", "fish" or "none"
+/// shell_history_file = "~/.zsh_history" # defaults to $HISTFILE or the shell's own file
+/// import_ignore = ["ls", "cd"] # programs `cmd import history` leaves out
+/// discover_tasks = true # offer the Makefile, package.json, Cargo and justfile tasks around
+/// plugins = ["~/bin/k8s-values"] # besides the `cmd-plugin-*` executables on $PATH
+/// trusted_projects = ["~/work/api"] # directories whose `.cmd.toml` providers may run
+///
+/// # Commands whose output lines are offered for placeholders with that name
+/// [providers]
+/// branch = "git branch --format='%(refname:short)'"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub ranking: Ranking,
+    /// Seconds a value provider, or a plugin request, can run before it is killed
+    pub provider_timeout: u64,
+    pub providers: HashMap<String, String>,
+    /// Expand environment variables in commands before running them
+    pub expand_env: bool,
+    /// Record commands in the history as they ran, with the variables expanded. Turning it off
+    /// keeps their values, secrets among them, out of the history.
+    pub record_expanded: bool,
+    pub execution: Execution,
+    pub shell: Option<String>,
+    /// Format of the shell history executed commands are added to
+    pub shell_history: ShellHistory,
+    pub shell_history_file: Option<String>,
+    /// Commands running these programs are too trivial to be imported from the shell history
+    pub import_ignore: Vec<String>,
+    /// Offer the tasks of the build files in the current directory along with saved commands
+    pub discover_tasks: bool,
+    /// Plugin executables that are not on `$PATH` or not named `cmd-plugin-*`
+    pub plugins: Vec<String>,
+    /// Directories whose `.cmd.toml` can run its own providers. Anyone who can commit to a
+    /// project can write one, so they are ignored until the user trusts it.
+    pub trusted_projects: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            ranking: Ranking::default(),
+            provider_timeout: 5,
+            providers: HashMap::new(),
+            expand_env: true,
+            record_expanded: true,
+            execution: Execution::default(),
+            shell: None,
+            shell_history: ShellHistory::default(),
+            shell_history_file: None,
+            import_ignore: ["ls", "ll", "cd", "pwd", "clear", "exit", "history", "cmd"]
+                .iter()
+                .map(|program| program.to_string())
+                .collect(),
+            discover_tasks: true,
+            plugins: Vec::new(),
+            trusted_projects: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn provider_timeout(&self) -> Duration {
+        Duration::from_secs(self.provider_timeout)
+    }
+
+    /// The configured shell, or the user's `$SHELL`, or `sh`
+    pub fn shell(&self) -> String {
+        self.shell
+            .clone()
+            .or_else(|| std::env::var("SHELL").ok().filter(|shell| !shell.is_empty()))
+            .unwrap_or_else(|| "sh".to_string())
+    }
+
+    /// The trusted project directories, with `~` expanded
+    pub fn trusted_projects(&self) -> Vec<PathBuf> {
+        match home::home_dir() {
+            Some(home) => self.trusted_projects.iter().map(|dir| expand_home(dir, &home)).collect(),
+            None => self.trusted_projects.iter().map(PathBuf::from).collect(),
+        }
+    }
+
+    pub fn load() -> Result<Config, CmdError> {
+        let mut home = home::home_dir().expect("Could not find home dir");
+        home.push(".cmd");
+        Config::from_file(&home.join("config.toml"))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config, CmdError> {
+        if !path.is_file() {
+            return Ok(Config::default());
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        Config::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Config, CmdError> {
+        toml::from_str(content).map_err(|err| CmdError::ConfigError(err.to_string()))
+    }
+}
@@ -8,6 +8,15 @@ pub enum CmdError {
     #[error("CSV error")] CSVError(String),
     #[error("SQL error")] SQLError(String),
     #[error("Git error")] GitError(String),
+    #[error("Database migration error: {0}")] MigrationError(String),
+    #[error("Invalid configuration: {0}")] ConfigError(String),
+    #[error("Invalid tag: {0}")] InvalidTag(String),
+    #[error("Invalid date: {0}")] InvalidDate(String),
+    #[error("Environment variable error: {0}")] EnvVarError(String),
+    #[error("Plugin error: {0}")] PluginError(String),
+    #[error("No command matched the pattern")] NoMatchError,
+    #[error("No command was selected")] NoSelectionError,
+    #[error("The command exited with code {0}")] ExitCodeError(i32),
     #[error("Failed serializing/deserializing record")] CSVSerdeError(String),
 }
 
@@ -1,12 +1,13 @@
 use std::io::Write;
 use termcolor::{ Color, ColorChoice, ColorSpec, StandardStream, WriteColor };
 
+/// Logs go to stderr, so that stdout only has what commands print, e.g. `cmd --print`
 pub fn print_text(color: Color, text: &str) {
-    let mut stdout = StandardStream::stdout(ColorChoice::Always);
-    stdout.set_color(ColorSpec::new().set_fg(Some(color))).unwrap();
-    writeln!(&mut stdout, "\n{}", text).unwrap();
-    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Black))).unwrap();
-    stdout.flush().unwrap();
+    let mut stderr = StandardStream::stderr(ColorChoice::Always);
+    stderr.set_color(ColorSpec::new().set_fg(Some(color))).unwrap();
+    writeln!(&mut stderr, "\n{}", text).unwrap();
+    stderr.set_color(ColorSpec::new().set_fg(Some(Color::Black))).unwrap();
+    stderr.flush().unwrap();
 }
 
 #[macro_export]
@@ -14,7 +15,7 @@ macro_rules! log_debug {
     ($($arg:tt)*) => {
         if log::log_enabled!(log::Level::Debug) {
             let st = format!($($arg)*);
-            println!("{} {}",file!(), line!());
+            eprintln!("{} {}",file!(), line!());
             $crate::logging::print_text(termcolor::Color::Rgb(128,128,128), &format!("{}:({}):{}", file!(), line!(), &st));
         }
     };
@@ -1,31 +1,44 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, io::{ self, Write }, rc::Rc};
 
 use clap_complete::{ Generator, generate_to };
-use cmd::{cmd_get::GetHandler, cmd_add::AddHandler, cmd_clear::ClearHandler, cmd_delete::DeleteHandler};
+use cmd::{
+    cmd_get::GetHandler,
+    cmd_add::AddHandler,
+    cmd_clear::ClearHandler,
+    cmd_delete::DeleteHandler,
+    cmd_history::{ HistoryHandler, HistoryQuery },
+    cmd_import::ImportHandler,
+    cmd_init::InitHandler,
+    cmd_suggest::{ SuggestHandler, SuggestQuery },
+    cmd_tag::TagHandler,
+};
 use env_logger::Builder;
 use log::LevelFilter;
 extern crate derive_builder;
-#[macro_use]
-extern crate lazy_static;
 
 use clap::{ Parser, CommandFactory, Command };
 use services::{
     controller::{ Controller },
     file_manager::{ FileManagerBuilder },
-    os_service::OSServiceImpl,
+    os_service::{ DryRunOSService, OSServiceImpl },
     cmd_service_sql::CmdServiceSQL,
-    // cmd_extension_git::CmdExtensionGit,
+    location::Location,
+    cmd_extension_git::CmdExtensionGit,
+    extension_registry::ExtensionRegistry,
+    plugin::{ self, PluginExtension },
+    task_discovery::TaskDiscovery,
+    project_commands::ProjectCommands,
 };
 use traits::{
     file_manager::FileManager,
     inputable::Inputable,
     os_service::OSService,
-    // cmd_extension::CmdExtension,
 };
 
 use crate::services::input::InputManager;
 
 mod args;
+mod config;
 mod logging;
 mod program;
 mod cmd;
@@ -36,19 +49,26 @@ mod cmd_csv;
 mod traits;
 mod error;
 mod models;
-use args::{ Cli, Commands };
+use args::{ Cli, Commands, ImportSource, TagAction };
+use config::Config;
+use error::CmdError;
 
 pub struct Deps {
     pub input: Rc<dyn Inputable>,
     pub args: Cli,
+    pub config: Config,
     pub controller: Controller<CmdServiceSQL>,
     pub os: Rc<dyn OSService>,
+    pub extensions: ExtensionRegistry,
+    pub tasks: TaskDiscovery,
+    pub project: ProjectCommands,
 }
 
 impl <'a> Deps {
-    fn new(args: Cli) -> Self {
+    /// Fails when the database cannot be opened or brought up to date, e.g. when a newer
+    /// version of cmd wrote it
+    fn new(args: Cli) -> Result<Self, CmdError> {
         let input: InputManager = InputManager {};
-        let os_service = OSServiceImpl {};
 
         let all_file_mgr = FileManagerBuilder::new("cmd.csv".to_string()).build();
         let used_file_mgr = FileManagerBuilder::new("cmd_used.csv".to_string()).build();
@@ -56,15 +76,52 @@ impl <'a> Deps {
         all_file_mgr.create_cmd_file().expect("Cannot create config file");
         used_file_mgr.create_cmd_file().expect("Cannot create config file");
 
-        let all_cmd_service = CmdServiceSQL::build_cmd_service(None).unwrap();
+        let all_cmd_service = CmdServiceSQL::build_cmd_service(None)?;
 
+        let config = Config::load().unwrap_or_else(|err| {
+            log_warn!("Ignoring the configuration file: {}", err.to_string());
+            Config::default()
+        });
+        let os_service = OSServiceImpl {
+            execution: config.execution,
+            shell: config.shell(),
+            history: config.shell_history.build(config.shell_history_file.as_deref()),
+        };
+        let os: Rc<dyn OSService> = match args.dry_run {
+            true => Rc::new(DryRunOSService { inner: os_service }),
+            false => Rc::new(os_service),
+        };
 
-        Self {
+        let mut extensions = ExtensionRegistry::default();
+        let mut tasks = TaskDiscovery::default();
+        let mut project = ProjectCommands::default();
+        if let Ok(dir) = std::env::current_dir() {
+            project = ProjectCommands::load(&dir, &config.trusted_projects());
+            if config.discover_tasks {
+                tasks = TaskDiscovery::new(dir.clone());
+            }
+            extensions.register(Box::new(CmdExtensionGit::new(dir)));
+        }
+        let path = std::env::var_os("PATH");
+        for plugin in plugin::discover(path.as_deref(), &config.plugins) {
+            extensions.register(Box::new(PluginExtension::new(plugin, config.provider_timeout())));
+        }
+
+        Ok(Self {
+            extensions,
+            tasks,
+            project,
+            controller: Controller {
+                all: all_cmd_service.clone(),
+                used: all_cmd_service,
+                ranking: config.ranking.build(),
+                dry_run: args.dry_run,
+            },
             args,
-            controller: Controller { all: all_cmd_service.clone(), used: all_cmd_service },
+            config,
             input: Rc::new(input),
-            os: Rc::new(os_service),
-        }
+            os,
+        })
     }
 }
 
@@ -93,57 +150,113 @@ fn main() {
 
     Builder::new().filter_level(level).init();
 
-    app(Deps::new(args))
+    match Deps::new(args) {
+        Ok(deps) => std::process::exit(app(deps)),
+        Err(err) => {
+            log_error!("Error: {}", err.to_string());
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs the requested command and returns the exit code for the process: the executed command's
+/// own status when there is one, 1 for any other failure
+pub(crate) fn app(deps: Deps) -> i32 {
+    app_with_output(deps, Box::new(io::stdout()))
 }
 
-pub(crate) fn app(deps: Deps) {
+/// `app`, with what `cmd --print` prints going to `output`
+pub(crate) fn app_with_output(deps: Deps, output: Box<dyn Write>) -> i32 {
     let mut args = deps.args.clone();
+    let print = args.print;
 
     let command = &mut args.command;
 
     let cmd: Commands = match command {
         Some(c) => c.clone(),
-        None => Commands::Get { pattern: args.get_command },
+        None => Commands::Get { pattern: args.get_command, tags: Vec::new() },
     };
 
     let deps_ref = Rc::new(RefCell::new(deps));
 
-    let mut get_handler = GetHandler::new(Rc::clone(&deps_ref));
+    let mut get_handler = GetHandler::new(Rc::clone(&deps_ref)).with_output(output);
     let mut add_handler = AddHandler::new(Rc::clone(&deps_ref));
     let clear_handler = ClearHandler::new(Rc::clone(&deps_ref));
     let mut delete_handler = DeleteHandler::new(Rc::clone(&deps_ref));
+    let history_handler = HistoryHandler::new(Rc::clone(&deps_ref));
+    let tag_handler = TagHandler::new(Rc::clone(&deps_ref));
+    let init_handler = InitHandler::new();
+    let import_handler = ImportHandler::new(Rc::clone(&deps_ref));
+    let suggest_handler = SuggestHandler::new(Rc::clone(&deps_ref));
 
-    match cmd {
-        Commands::Get { pattern } => {
-            match get_handler.get_command(&pattern) {
-                Ok(_) => { log_info!("Completed successfully.") }
-                Err(err) => {
-                    log_error!("Error: {}", err.to_string());
+    let result = match cmd {
+        Commands::Get { pattern, tags: _ } => {
+            // The printed command is all the shell widget should read
+            get_handler.get_command(&pattern).map(|_| {
+                if !print {
+                    log_info!("Completed successfully.");
                 }
-            }
+            })
         }
         Commands::Add { pattern, execute } => {
-            match add_handler.add_command(pattern, execute) {
-                Ok(_) => { log_info!("Completed successfully.") }
-                Err(err) => {
-                    log_error!("Error: {}", err.to_string());
-                }
-            }
+            add_handler.add_command(pattern, execute).map(|_| log_info!("Completed successfully."))
         }
         Commands::Clear {} => {
             clear_handler.clear();
+            Ok(())
+        }
+        Commands::History { pattern, dir, since, until, failed, limit } => {
+            let dir = match (dir, args.here) {
+                (None, true) => Some(Location::current().project_root().to_string()),
+                (dir, _) => dir,
+            };
+            let query = HistoryQuery { pattern, dir, since, until, failed, limit };
+            history_handler.print_history(query)
+        }
+        Commands::Init { shell } => {
+            init_handler.print_init(shell);
+            Ok(())
+        }
+        Commands::Import { source: ImportSource::History { shell, file, limit } } => {
+            import_handler.import_history(shell, file, limit)
+        }
+        Commands::Suggest { shell, file, recent, limit } => {
+            suggest_handler.suggest(SuggestQuery { shell, file, recent, limit })
+        }
+        Commands::Tag { action } => {
+            match action {
+                TagAction::Add { tag, pattern } => tag_handler.add_tag(&tag, pattern),
+                TagAction::Remove { tag, pattern } => tag_handler.remove_tag(&tag, pattern),
+                TagAction::List { tag } => tag_handler.print_tags(tag),
+            }
         }
         Commands::Debug { pattern: _ } => {
             let ctrl = &deps_ref.as_ref().borrow().controller;
             ctrl.debug();
+            Ok(())
         }
         Commands::Delete {} => {
-            match delete_handler.delete_command() {
-                Ok(_) => { log_info!("Completed successfully.") }
-                Err(err) => {
-                    log_error!("Error: {}", err.to_string());
-                }
-            }
+            delete_handler.delete_command().map(|_| log_info!("Completed successfully."))
+        }
+    };
+
+    match result {
+        Ok(_) => 0,
+        Err(CmdError::ExitCodeError(code)) => {
+            log_debug!("The command exited with code {}", code);
+            code
+        }
+        Err(CmdError::NoMatchError) => {
+            log_warn!("No command matched the pattern");
+            1
+        }
+        Err(CmdError::NoSelectionError) => {
+            log_info!("No command was selected. Exiting...");
+            1
+        }
+        Err(err) => {
+            log_error!("Error: {}", err.to_string());
+            1
         }
     }
-}
\ No newline at end of file
+}
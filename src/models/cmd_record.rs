@@ -3,7 +3,9 @@ use serde::{ Serialize, Deserialize };
 use rusqlite::Row;
 use std::hash::Hash;
 
-#[derive(Debug, Deserialize, Serialize, Eq, Clone)]
+use crate::models::history_record::timestamp_now;
+
+#[derive(Debug, Deserialize, Serialize, Eq, Clone, Default)]
 #[serde(rename_all = "PascalCase")]
 pub struct CmdRecord {
     #[serde(rename = "id")]
@@ -12,6 +14,29 @@ pub struct CmdRecord {
     pub command: String,
     #[serde(rename = "used_times")]
     pub used_times: usize,
+    /// Seconds since the UNIX epoch of the last execution, if it was ever executed
+    #[serde(rename = "last_used", default)]
+    pub last_used: Option<i64>,
+    #[serde(rename = "description", default)]
+    pub description: Option<String>,
+    /// Tag names, sorted. Only stored in SQLite.
+    #[serde(skip)]
+    pub tags: Vec<String>,
+    #[serde(skip)]
+    pub source: CmdSource,
+}
+
+/// Where a command offered for selection comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CmdSource {
+    /// Stored by cmd
+    #[default]
+    Saved,
+    /// A task found in the build files of the current directory, only stored once it is used
+    Discovered,
+    /// From a `.cmd.toml` file of the project, only stored once it is used. The inline
+    /// providers of its placeholders only run if the file is trusted.
+    Project { trusted: bool },
 }
 
 pub trait CmdRecordIterable {
@@ -103,6 +128,18 @@ impl CmdRecord {
 
     pub fn increase_usage(mut self: &mut Self) {
         self.used_times += 1;
+        self.last_used = Some(timestamp_now());
+    }
+
+    /// The command as shown in selection lists, followed by its description as a shell comment.
+    /// Always starts with `command`, so highlighted positions in it stay valid.
+    pub fn display(&self) -> String {
+        match &self.description {
+            Some(description) if !description.is_empty() => {
+                format!("{}  # {}", self.command, description)
+            }
+            _ => self.command.clone(),
+        }
     }
 }
 
@@ -112,6 +149,22 @@ impl From<&Row<'_>> for CmdRecord {
             id: row.get("id").expect("Could not parse ID"),
             command: row.get("command").expect("Could not parse command"),
             used_times: row.get("used_times").expect("Could not parse used_time"),
+            last_used: row.get("last_used").expect("Could not parse last_used"),
+            description: row.get("description").expect("Could not parse description"),
+            tags: parse_tags(row.get("tags").expect("Could not parse tags")),
+            source: CmdSource::Saved,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Tags come from SQLite as a single comma separated value, in no particular order
+fn parse_tags(tags: Option<String>) -> Vec<String> {
+    let mut tags = tags
+        .unwrap_or_default()
+        .split(',')
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| tag.to_string())
+        .collect::<Vec<_>>();
+    tags.sort();
+    tags
+}
@@ -0,0 +1,30 @@
+use std::{ os::unix::process::ExitStatusExt, process::ExitStatus, time::Duration };
+
+/// How an executed command ended
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionOutcome {
+    /// `None` when the command was terminated by a signal
+    pub code: Option<i32>,
+    /// The signal that terminated the command, if any
+    pub signal: Option<i32>,
+    pub duration: Duration,
+}
+
+impl ExecutionOutcome {
+    pub fn from_status(status: ExitStatus, duration: Duration) -> Self {
+        ExecutionOutcome { code: status.code(), signal: status.signal(), duration }
+    }
+
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// What a shell would report as `$?`: the exit code, or 128 plus the signal number
+    pub fn exit_code(&self) -> i32 {
+        match (self.code, self.signal) {
+            (Some(code), _) => code,
+            (None, Some(signal)) => 128 + signal,
+            (None, None) => 1,
+        }
+    }
+}
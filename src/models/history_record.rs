@@ -0,0 +1,49 @@
+use std::time::{ SystemTime, UNIX_EPOCH };
+
+use rusqlite::Row;
+
+/// Current time in seconds since the UNIX epoch, as stored in the database
+pub fn timestamp_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// A single execution of a command, as recorded in the `history` table.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HistoryRecord {
+    pub id: usize,
+    /// The saved command this execution was started from, if it still exists
+    pub cmd_id: Option<usize>,
+    /// The fully expanded command line that was executed
+    pub command: String,
+    pub cwd: String,
+    /// Root of the git repository `cwd` belongs to, if any
+    pub repo_root: Option<String>,
+    /// `None` when the process was terminated by a signal
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// Seconds since the UNIX epoch
+    pub executed_at: i64,
+    /// `executed_at` formatted in local time. Only set on records read back from storage
+    pub executed_at_local: String,
+}
+
+impl From<&Row<'_>> for HistoryRecord {
+    fn from(row: &Row<'_>) -> Self {
+        HistoryRecord {
+            id: row.get("id").expect("Could not parse ID"),
+            cmd_id: row.get("cmd_id").expect("Could not parse cmd_id"),
+            command: row.get("command").expect("Could not parse command"),
+            cwd: row.get("cwd").expect("Could not parse cwd"),
+            repo_root: row.get("repo_root").expect("Could not parse repo_root"),
+            exit_code: row.get("exit_code").expect("Could not parse exit_code"),
+            duration_ms: row.get("duration_ms").expect("Could not parse duration_ms"),
+            executed_at: row.get("executed_at").expect("Could not parse executed_at"),
+            executed_at_local: row
+                .get("executed_at_local")
+                .expect("Could not parse executed_at_local"),
+        }
+    }
+}
@@ -1 +1,3 @@
-pub mod cmd_record;
\ No newline at end of file
+pub mod cmd_record;
+pub mod history_record;
+pub mod execution_outcome;
\ No newline at end of file
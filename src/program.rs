@@ -1,30 +1,207 @@
-use std::process::Command;
+use std::{ os::unix::process::ExitStatusExt, process::{ Command, ExitStatus } };
 
-use crate::log_debug;
+use crate::{ error::CmdError, log_debug };
 
-pub(crate) fn parse_program(program: &str) -> Command {
-    let n = program.replace("\n", "");
-    let parts = n.split(" ");
-    let vec = parts.collect::<Vec<&str>>();
-    let mut iter = vec.iter();
+/// How a program is chained to the one before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    /// `;`, or the first program: always runs
+    Sequence,
+    /// `&&`: only runs if the previous program succeeded
+    And,
+    /// `||`: only runs if the previous program failed
+    Or,
+}
+
+/// One program of a command line, for direct execution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub operator: Operator,
+    pub args: Vec<String>,
+}
+
+impl Program {
+    pub fn command(&self) -> Command {
+        let mut output = Command::new(&self.args[0]);
+        output.args(&self.args[1..]);
+        log_debug!("Program: {:?}", output);
+        output
+    }
+
+    /// Whether the program runs, given how the previous one ended
+    pub fn should_run(&self, previous_succeeded: bool) -> bool {
+        match self.operator {
+            Operator::Sequence => true,
+            Operator::And => previous_succeeded,
+            Operator::Or => !previous_succeeded,
+        }
+    }
+}
 
-    let cmd = match iter.next() {
-        Some(inner_cmd) => inner_cmd,
-        None => "echo",
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Operator(Operator),
+}
+
+/// Splits a command line into programs chained by `&&`, `||` and `;`.
+///
+/// Words follow the shell quoting rules: `'single quotes'` are literal, `"double quotes"` allow
+/// `\"`, `\\` and `\$` escapes, and a backslash outside of quotes escapes the next char. Pipes,
+/// redirects, subshells and background jobs need a shell, so they are rejected.
+pub fn parse_programs(program: &str) -> Result<Vec<Program>, CmdError> {
+    let mut programs: Vec<Program> = Vec::new();
+    let mut current = Program { operator: Operator::Sequence, args: Vec::new() };
+
+    for token in tokenize(program)? {
+        match token {
+            Token::Word(word) => current.args.push(word),
+            // Blank lines and repeated `;` separate nothing
+            Token::Operator(Operator::Sequence) if current.args.is_empty() => {}
+            Token::Operator(operator) => {
+                if current.args.is_empty() {
+                    return Err(
+                        CmdError::BaseError(format!("Syntax error near an operator in '{}'", program))
+                    );
+                }
+                programs.push(current);
+                current = Program { operator, args: Vec::new() };
+            }
+        }
+    }
+
+    match (current.args.is_empty(), current.operator) {
+        (false, _) => programs.push(current),
+        // A trailing `;` is fine, a trailing `&&` or `||` is not
+        (true, Operator::Sequence) => {}
+        (true, _) => {
+            return Err(CmdError::BaseError(format!("Unexpected end of command in '{}'", program)));
+        }
+    }
+
+    Ok(programs)
+}
+
+/// Status of a program that could not be started, as a shell reports a command it cannot find
+const NOT_FOUND_STATUS: i32 = 127;
+
+/// Runs the programs one after the other, honouring `&&` and `||`. Returns how the last program
+/// that ran ended. A program that cannot be started fails with status 127, like in a shell.
+pub fn run_programs(programs: Vec<Program>) -> Result<ExitStatus, CmdError> {
+    let mut last: Option<ExitStatus> = None;
+
+    for program in programs {
+        let previous_succeeded = last.is_none_or(|status| status.success());
+        if !program.should_run(previous_succeeded) {
+            log_debug!("Skipping {:?}", program.args);
+            continue;
+        }
+        let status = program.command().status().unwrap_or_else(|err| {
+            eprintln!("{}: {}", program.args[0], err);
+            ExitStatus::from_raw(NOT_FOUND_STATUS << 8)
+        });
+        last = Some(status);
+    }
+
+    last.ok_or_else(|| CmdError::BaseError("No command to execute".to_string()))
+}
+
+fn tokenize(program: &str) -> Result<Vec<Token>, CmdError> {
+    let chars: Vec<char> = program.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    // `None` until a word starts, so that `''` is still an (empty) argument
+    let mut word: Option<String> = None;
+    let mut i = 0;
+
+    let unsupported = |c: char| {
+        CmdError::BaseError(
+            format!("'{}' needs a shell, set `execution = \"shell\"` in the configuration", c)
+        )
     };
 
-    let mut output = Command::new(cmd);
-    for arg in iter {
-        output.arg(arg);
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\'' => {
+                let end = find(&chars, i + 1, '\'').ok_or_else(|| unterminated(program))?;
+                word.get_or_insert_with(String::new).extend(&chars[i + 1..end]);
+                i = end + 1;
+            }
+            '"' => {
+                let current = word.get_or_insert_with(String::new);
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(unterminated(program));
+                        }
+                        Some('"') => {
+                            break;
+                        }
+                        Some('\\') if matches!(chars.get(i + 1), Some('"' | '\\' | '$' | '`')) => {
+                            current.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(c) => {
+                            current.push(*c);
+                            i += 1;
+                        }
+                    }
+                }
+                i += 1;
+            }
+            '\\' => {
+                match chars.get(i + 1) {
+                    // A line continuation
+                    Some('\n') => {}
+                    Some(next) => word.get_or_insert_with(String::new).push(*next),
+                    None => word.get_or_insert_with(String::new).push('\\'),
+                }
+                i += 2;
+            }
+            '&' if chars.get(i + 1) == Some(&'&') => {
+                push_word(&mut tokens, &mut word);
+                tokens.push(Token::Operator(Operator::And));
+                i += 2;
+            }
+            '|' if chars.get(i + 1) == Some(&'|') => {
+                push_word(&mut tokens, &mut word);
+                tokens.push(Token::Operator(Operator::Or));
+                i += 2;
+            }
+            ';' | '\n' => {
+                push_word(&mut tokens, &mut word);
+                tokens.push(Token::Operator(Operator::Sequence));
+                i += 1;
+            }
+            '|' | '&' | '<' | '>' | '(' | ')' | '`' => {
+                return Err(unsupported(c));
+            }
+            c if c.is_whitespace() => {
+                push_word(&mut tokens, &mut word);
+                i += 1;
+            }
+            c => {
+                word.get_or_insert_with(String::new).push(c);
+                i += 1;
+            }
+        }
+    }
+
+    push_word(&mut tokens, &mut word);
+    Ok(tokens)
+}
+
+fn push_word(tokens: &mut Vec<Token>, word: &mut Option<String>) {
+    if let Some(word) = word.take() {
+        tokens.push(Token::Word(word));
     }
-    log_debug!("Program: {:?}", output);
-    return output;
 }
 
-pub fn parse_programs(program: &str) -> Vec<Command> {
-    program
-        .split("&&")
-        .map(|cmd| cmd.trim())
-        .map(|cmd| parse_program(cmd))
-        .collect::<Vec<_>>()
-}
\ No newline at end of file
+fn find(chars: &[char], from: usize, target: char) -> Option<usize> {
+    (from..chars.len()).find(|&i| chars[i] == target)
+}
+
+fn unterminated(program: &str) -> CmdError {
+    CmdError::BaseError(format!("Unterminated quote in '{}'", program))
+}
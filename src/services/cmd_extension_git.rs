@@ -1,197 +1,222 @@
-use std::{ env, str::{ from_utf8 }, ops::Range };
+use std::path::PathBuf;
+
+use git2::{ BranchType, Repository, Sort };
+use regex::Regex;
 
 use crate::{
-    traits::cmd_extension::CmdExtension,
-    log_info,
-    log_debug,
-    models::cmd_record::CmdRecord,
-    Deps,
     error::CmdError,
+    traits::cmd_extension::{ CmdExtension, PlaceholderContext, PlaceholderValue },
+    log_debug,
 };
-use git2::{ Repository, BranchType };
-use regex::bytes::Regex;
 
-pub struct CmdExtensionGit {}
+/// How many commits are offered, newest first
+const RECENT_COMMITS: usize = 30;
+
+/// Flags whose value is a new name or a message, not something that exists in the repository
+const VALUE_FLAGS: [&str; 6] = ["-b", "-B", "-c", "-C", "-m", "--orphan"];
+
+/// What a git argument can be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitValues {
+    LocalBranches,
+    /// `origin/main` and the like
+    RemoteBranches,
+    Remotes,
+    Tags,
+    /// The short hash, described by the subject line
+    Commits,
+    /// The path, described by the name
+    Worktrees,
+}
 
-impl CmdExtension for CmdExtensionGit {
-    fn handle_if_match(
-        self,
-        pattern: &Option<CmdRecord>,
-        deps: &mut crate::Deps
-    ) -> Option<Result<(), crate::error::CmdError>> {
-        if let Some(cmd_record) = pattern {
-            let cmd = cmd_record.command.clone();
-            match cmd.as_str() {
-                "git checkout {}" => {
-                    if let Some(value) = fill_with_branch(deps, cmd, cmd_record) {
-                        return value;
-                    }
-                    return None;
-                }
-                "git checkout {} && git pull --rebase && git checkout {}" => {
-                    if let Some(value) = fill_with_branch(deps, cmd, cmd_record) {
-                        return value;
-                    }
-                    return None;
-                }
-                "git checkout {} && git pull --rebase && git checkout {} && git merge {}" => {
-                    if let Some(value) = fill_with_branch(deps, cmd, cmd_record) {
-                        return value;
-                    }
-                    return None;
-                }
-                _ => {}
+/// Offers what the repository has for the placeholders of git commands: branches to check out
+/// or merge, remotes to push to, tags, commits to cherry-pick and worktrees. Outside of a
+/// repository it offers nothing, so the value is typed instead.
+pub struct CmdExtensionGit {
+    /// Where the repository is looked for, walking up
+    pub dir: PathBuf,
+    pattern: Regex,
+}
+
+impl CmdExtensionGit {
+    pub fn new(dir: PathBuf) -> Self {
+        CmdExtensionGit { dir, pattern: Regex::new(r"\bgit\s").expect("Invalid git pattern") }
+    }
+
+    fn open(&self) -> Option<Repository> {
+        match Repository::discover(&self.dir) {
+            Ok(repo) => Some(repo),
+            Err(err) => {
+                log_debug!("Not in a git repository: {}", err);
+                None
             }
         }
-        None
     }
-}
 
-fn fill_with_branch(
-    deps: &mut crate::Deps,
-    cmd: String,
-    cmd_record: &CmdRecord
-) -> Option<Option<Result<(), crate::error::CmdError>>> {
-    let repo = match
-        Repository::open(env::current_dir().expect("Cannot get the current directory"))
-    {
-        Ok(repo) => repo,
-        Err(e) => panic!("failed to clone: {}", e),
-    };
-    let _branches = get_branches(&repo).expect("cannot retrieve branches");
-    // let selected_branch = deps.input
-    //     .select_option(&branches, Some("Select a branch to checkout".to_string()))
-    //     .unwrap();
-    // let note = branches.get(selected_branch).unwrap();
-    //let final_cmd = cmd.replacen("{}", &note, 1);
-    let parsed_cmd = String::from(&cmd);
-    let (final_cmd, _count) = fill_placeholders(&cmd, deps, parsed_cmd, &mut cmd_record.clone());
-
-    log_debug!("Executing '{}'!", &final_cmd);
-    // let result = deps.os.execute_command(&final_cmd);
-    let result: Result<bool, CmdError> = Ok(true);
-    let _used_commands = deps.controller.get_used_commands(cmd.clone());
-    match result {
-        Ok(_) => {
-            log_info!("Finalized successfully");
-            let mut new_cmd = cmd_record.to_owned().clone();
-            // new_cmd.update_command(&final_cmd);
-            let alias = if final_cmd.eq(&cmd_record.command) { None } else { Some(final_cmd) };
-            new_cmd.used_times = cmd_record.used_times + 1;
-            let used_command = &mut deps.controller.add_used_command(new_cmd.to_owned(), alias);
-            if let Ok(_cmd) = used_command {
-                return Some(Some(Ok(())));
+    fn list(&self, repo: &Repository, kind: GitValues) -> Result<Vec<PlaceholderValue>, CmdError> {
+        let values = match kind {
+            GitValues::LocalBranches => branch_names(repo, BranchType::Local)?,
+            GitValues::RemoteBranches => {
+                branch_names(repo, BranchType::Remote)?
+                    .into_iter()
+                    .filter(|branch| !branch.ends_with("/HEAD"))
+                    .collect()
             }
-        }
-        Err(err) => {
-            log_info!("Finalized with an error: {:?}", err);
-            return Some(None);
-        }
+            GitValues::Remotes => {
+                repo.remotes()?
+                    .iter()
+                    .flatten()
+                    .map(|remote| remote.to_string())
+                    .collect()
+            }
+            GitValues::Tags => {
+                repo.tag_names(None)?
+                    .iter()
+                    .flatten()
+                    .map(|tag| tag.to_string())
+                    .collect()
+            }
+            GitValues::Commits => {
+                return recent_commits(repo);
+            }
+            GitValues::Worktrees => {
+                let mut worktrees = Vec::new();
+                for name in repo.worktrees()?.iter().flatten() {
+                    let worktree = repo.find_worktree(name)?;
+                    worktrees.push(
+                        PlaceholderValue::described(worktree.path().to_string_lossy(), name)
+                    );
+                }
+                return Ok(worktrees);
+            }
+        };
+
+        Ok(values.into_iter().map(PlaceholderValue::new).collect())
     }
-    None
 }
 
-fn get_branches(repo: &Repository) -> Result<Vec<String>, crate::error::CmdError> {
-    let branches = repo.branches(Some(BranchType::Local))?;
-
-    let all = branches
-        .map(|branch| branch.expect("Open branch"))
-        .map(|b| b.0)
-        .map(|b| {
-            match b.name() {
-                Ok(name) =>
-                    match name {
-                        Some(name) => name.to_string(),
-                        None => String::new(),
-                    }
-                Err(_) => String::new(),
-            }
-        })
-        .collect::<Vec<String>>();
+impl CmdExtension for CmdExtensionGit {
+    fn name(&self) -> &str {
+        "git"
+    }
 
-    log_info!("branches: {:?}", all);
+    fn pattern(&self) -> &Regex {
+        &self.pattern
+    }
+
+    fn placeholder_values(&self, context: &PlaceholderContext) -> Option<Vec<PlaceholderValue>> {
+        let kinds = argument_values(&context.before);
+        if kinds.is_empty() {
+            return None;
+        }
+        let repo = self.open()?;
 
-    Ok(all)
+        let mut values = Vec::new();
+        for kind in kinds {
+            match self.list(&repo, kind) {
+                Ok(listed) => values.extend(listed),
+                Err(err) => log_debug!("Could not list the {:?}: {}", kind, err),
+            }
+        }
+        Some(values)
+    }
 }
 
-pub fn fill_placeholders(
-    selected_cmd: &String,
-    deps: &mut Deps,
-    mut parsed_cmd: String,
-    selected_record: &mut CmdRecord
-) -> (String, usize) {
-    let (final_cmd, final_count) = match selected_cmd.contains("{}") {
-        true => {
-            log_debug!("Fill placeholders");
-
-            let repo = match
-                Repository::open(env::current_dir().expect("Cannot get the current directory"))
-            {
-                Ok(repo) => repo,
-                Err(e) => panic!("failed to clone: {}", e),
-            };
-            let branches = get_branches(&repo).expect("cannot retrieve branches");
-
-            let rest = selected_cmd.matches("{}");
-            let count = rest.count();
-
-            println!("Fill {} params...", count);
-
-            // let re = Regex::new(
-            //     r"git checkout (?P<b1>\{\}).+git checkout(?P<b2>\{\}).+git merge (?P<b3>\{\})"
-            // ).expect("Could not build regex");
-
-            let re = Regex::new(r"git (checkout|merge) (\{\})").expect("Could not build regex");
-
-            let names = re
-                .captures_iter(selected_cmd.as_bytes())
-                .map(|c| { c.get(1).map_or(&b""[..], |m| m.as_bytes()) })
-                .map(|c| from_utf8(c).unwrap().to_string())
-                .collect::<Vec<String>>();
-
-            log_info!("Names: {:?}", names);
-
-            let subcommands = selected_cmd.split("&&").clone();
-            let windows: &Vec<String> = &subcommands
-                .map(|subcmd| {
-                    let indeces: &Vec<usize> = &subcmd
-                        .match_indices("{}")
-                        .map(|i| i.0)
-                        //.map(|i| (0..i.0).map(|x| if x == i.0 { "v" } else { "-" }).collect::<String>())
-                        .collect();
-                    log_info!("find: {:?}", indeces);
-
-                    let mut indeces2 = vec![0];
-                    indeces2.extend(indeces);
-
-                    let windows: String = indeces2
-                        .windows(2)
-                        .map(|w| String::from(subcmd[Range { start: w[0], end: w[1] }].trim()))
-                        .collect::<Vec<String>>()
-                        .join("");
-                    windows
-                })
-                .collect();
-
-            log_info!("slices: {:?}", windows);
-
-            for i in 0..count {
-                let selected_branch = deps.input
-                    .select_option(
-                        &branches,
-                        Some(format!("Select a branch for {} ({})", windows[i], i).to_string())
-                    )
-                    .unwrap();
-                let note = branches.get(selected_branch).unwrap();
-                parsed_cmd = parsed_cmd.replacen("{}", &note, 1);
-                // print!("{esc}c", esc = 27 as char);
-                // print!("\r");
-                // print!("{}", 8u8 as char);
+/// What the next argument of the last git command in `before` can be, from its subcommand and
+/// the arguments it already has. Empty when it is not an argument git knows about, like a
+/// commit message or a new branch name.
+pub fn argument_values(before: &str) -> Vec<GitValues> {
+    let command = match before.rfind(['&', '|', ';']) {
+        Some(i) => &before[i + 1..],
+        None => before,
+    };
+    let words = command.split_whitespace().collect::<Vec<_>>();
+
+    let mut rest = match words.iter().rposition(|word| *word == "git") {
+        Some(i) => &words[i + 1..],
+        None => {
+            return Vec::new();
+        }
+    };
+    // Global options that come before the subcommand, like `git -C ../repo checkout`
+    while let Some(word) = rest.first() {
+        match *word {
+            "-C" | "-c" => {
+                rest = rest.get(2..).unwrap_or_default();
+            }
+            word if word.starts_with('-') => {
+                rest = &rest[1..];
+            }
+            _ => {
+                break;
             }
+        }
+    }
 
-            (parsed_cmd, 0)
+    let (subcommand, args) = match rest.split_first() {
+        Some((subcommand, args)) => (*subcommand, args),
+        None => {
+            return Vec::new();
         }
-        false => (parsed_cmd, selected_record.used_times),
     };
-    (final_cmd, final_count)
-}
\ No newline at end of file
+    if args.last().is_some_and(|flag| VALUE_FLAGS.contains(flag)) {
+        return Vec::new();
+    }
+    let flags = args
+        .iter()
+        .filter(|arg| arg.starts_with('-'))
+        .copied()
+        .collect::<Vec<_>>();
+    let positional = args
+        .iter()
+        .filter(|arg| !arg.starts_with('-'))
+        .copied()
+        .collect::<Vec<_>>();
+
+    use GitValues::*;
+    match (subcommand, positional.as_slice()) {
+        ("checkout" | "switch" | "merge" | "rebase", _) => vec![LocalBranches, RemoteBranches],
+        ("branch", _) if flags.iter().any(|f| matches!(*f, "-d" | "-D" | "--delete")) => vec![LocalBranches],
+        ("push" | "pull" | "fetch", []) => vec![Remotes],
+        ("push", _) => vec![LocalBranches, Tags],
+        ("pull" | "fetch", _) => vec![LocalBranches],
+        ("cherry-pick" | "revert" | "show" | "reset", _) => vec![Commits],
+        ("tag", _) if flags.iter().any(|f| matches!(*f, "-d" | "--delete" | "-v" | "--verify")) => vec![Tags],
+        // `git tag <new tag> <commit>`
+        ("tag", [_]) => vec![Commits],
+        ("worktree", ["remove" | "lock" | "unlock" | "move" | "repair"]) => vec![Worktrees],
+        // `git worktree add <path> <branch>`
+        ("worktree", ["add", _]) => vec![LocalBranches, RemoteBranches],
+        ("remote", ["remove" | "rm" | "rename" | "set-url" | "get-url" | "prune" | "show"]) => vec![Remotes],
+        _ => Vec::new(),
+    }
+}
+
+fn branch_names(repo: &Repository, kind: BranchType) -> Result<Vec<String>, CmdError> {
+    let mut branches = Vec::new();
+    for branch in repo.branches(Some(kind))? {
+        if let Some(name) = branch?.0.name()? {
+            branches.push(name.to_string());
+        }
+    }
+    Ok(branches)
+}
+
+fn recent_commits(repo: &Repository) -> Result<Vec<PlaceholderValue>, CmdError> {
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TIME)?;
+    walk.push_head()?;
+
+    let mut commits = Vec::new();
+    for oid in walk.take(RECENT_COMMITS) {
+        let commit = repo.find_commit(oid?)?;
+        let hash = commit.as_object().short_id()?;
+        commits.push(
+            PlaceholderValue::described(
+                hash.as_str().unwrap_or_default(),
+                commit.summary().unwrap_or_default()
+            )
+        );
+    }
+    Ok(commits)
+}
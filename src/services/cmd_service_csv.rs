@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::io::Read;
 use std::io::Write;
 use std::sync::atomic::AtomicUsize;
@@ -11,9 +12,12 @@ use crate::error::CmdError;
 use crate::log_debug;
 
 use crate::log_info;
+use crate::services::location::Location;
 use crate::models::cmd_record::CmdRecord;
+use crate::models::history_record::HistoryRecord;
 use crate::traits::cmd_service::CmdService;
 use crate::traits::cmd_service::SearchFilters;
+use crate::traits::cmd_service::HistoryFilters;
 use crate::traits::file_manager::{ FileManager };
 
 #[deny(warnings)]
@@ -65,7 +69,12 @@ impl<'a, T, V> CmdServiceCSV<'a, T, V> where T: Read, V: Write {
 impl<'a, T, V> CmdService for CmdServiceCSV<'a, T, V> where T: Read, V: Write {
     fn add_command(self: &mut Self, command: String) -> Result<(), CmdError> {
         let id = self.get_id();
-        let record = CmdRecord { id: id, command: String::from(&command), used_times: 1 };
+        let record = CmdRecord {
+            id: id,
+            command: String::from(&command),
+            used_times: 1,
+            ..CmdRecord::default()
+        };
         let exists = self.commands.iter().find(|x| x.command == record.command);
 
         if let Some(_) = exists {
@@ -111,6 +120,10 @@ impl<'a, T, V> CmdService for CmdServiceCSV<'a, T, V> where T: Read, V: Write {
         };
     }
 
+    fn search_full_text(self: &mut Self, _text: &str) -> Result<Vec<(CmdRecord, f64)>, CmdError> {
+        Err(unsupported())
+    }
+
     fn update_command(self: &mut Self, record: CmdRecord) -> Result<(), CmdError> {
         let record_exists = self.is_record_present(&record);
         let mut updated_commands = self
@@ -133,6 +146,7 @@ impl<'a, T, V> CmdService for CmdServiceCSV<'a, T, V> where T: Read, V: Write {
                     id: id,
                     command: String::from(&record.command),
                     used_times: 1,
+                    ..CmdRecord::default()
                 };
                 new_record
             }
@@ -161,14 +175,63 @@ impl<'a, T, V> CmdService for CmdServiceCSV<'a, T, V> where T: Read, V: Write {
     }
 
     fn insert_command(self: &mut Self, _command: CmdRecord) -> Result<(), CmdError> {
-        todo!()
+        Err(unsupported())
     }
 
     fn debug(self: &Self) {
         log_info!("No debug info")
     }
 
-    fn delete_command(self: &mut Self, command: CmdRecord) -> Result<(), CmdError> {
-        todo!()
+    fn delete_command(self: &mut Self, _command: CmdRecord) -> Result<(), CmdError> {
+        Err(unsupported())
+    }
+
+    fn add_history(self: &mut Self, _entry: HistoryRecord) -> Result<(), CmdError> {
+        Err(unsupported())
+    }
+
+    fn get_history(self: &mut Self, _filter: HistoryFilters) -> Result<Vec<HistoryRecord>, CmdError> {
+        Err(unsupported())
+    }
+
+    fn get_location_usage(
+        self: &mut Self,
+        _location: &Location
+    ) -> Result<HashMap<usize, usize>, CmdError> {
+        Err(unsupported())
+    }
+
+    fn add_tag(self: &mut Self, _cmd_id: usize, _tag: &str) -> Result<(), CmdError> {
+        Err(unsupported())
+    }
+
+    fn remove_tag(self: &mut Self, _cmd_id: usize, _tag: &str) -> Result<(), CmdError> {
+        Err(unsupported())
+    }
+
+    fn get_tags(self: &mut Self) -> Result<Vec<(String, usize)>, CmdError> {
+        Err(unsupported())
+    }
+
+    fn add_placeholder_value(
+        self: &mut Self,
+        _template: &str,
+        _name: &str,
+        _value: &str
+    ) -> Result<(), CmdError> {
+        Err(unsupported())
     }
-}
\ No newline at end of file
+
+    fn get_placeholder_values(
+        self: &mut Self,
+        _template: &str,
+        _name: &str
+    ) -> Result<Vec<String>, CmdError> {
+        Err(unsupported())
+    }
+}
+
+/// The CSV files predate these features, which only the SQLite store has
+fn unsupported() -> CmdError {
+    CmdError::BaseError("not supported by the CSV store".to_string())
+}
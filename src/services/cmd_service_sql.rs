@@ -1,17 +1,24 @@
 use std::{collections::HashMap, rc::Rc, borrow::Borrow};
 
-use rusqlite::{ Connection, params };
+use rusqlite::{ Connection, params, params_from_iter, ToSql };
 
 use crate::{
-    traits::{ cmd_service::{ CmdService, SearchFilters }, file_manager::FileManager },
+    traits::{
+        cmd_service::{ CmdService, SearchFilters, HistoryFilters, MatchMode, SearchOrder },
+        file_manager::FileManager,
+    },
     error::{ CmdError, self },
-    models::cmd_record::{ CmdRecord },
+    models::{ cmd_record::CmdRecord, history_record::{ HistoryRecord, timestamp_now } },
     log_debug,
     log_info,
-    services::cmd_service_csv::build_cmd_csv_service,
+    services::{ cmd_service_csv::build_cmd_csv_service, location::Location, migrations },
 };
 
 
+/// Columns read by `CmdRecord::from`, with the tags of each command as a comma separated value
+const CMD_COLUMNS: &str =
+    "cmd.id, cmd.command, cmd.used_times, cmd.last_used, cmd.description, (SELECT group_concat(tag.name, ',') FROM cmd_tag JOIN tag ON tag.id = cmd_tag.tag_id WHERE cmd_tag.cmd_id = cmd.id) AS tags";
+
 pub struct CmdServiceSQL {
     connection: Rc<Connection>,
 }
@@ -36,20 +43,12 @@ impl CmdServiceSQL {
             }
         };
 
-        // #[serde(rename = "id")]
-        // pub id: usize,
-        // #[serde(rename = "command")]
-        // pub command: String,
-        // #[serde(rename = "used_times")]
-        // pub used_times: usize,
-
-        connection.execute(
-            "
-        CREATE TABLE IF NOT EXISTS cmd (id INTEGER PRIMARY KEY, command TEXT UNIQUE, used_times INTEGER);
-        CREATE INDEX IF NOT EXISTS commands_ind ON cmd (command);
-        ",
-            []
-        )?;
+        let db_path = connection
+            .path()
+            .filter(|path| path.is_file())
+            .map(|path| path.to_path_buf());
+        migrations::migrate(&connection, db_path.as_deref())?;
+        connection.pragma_update(None, "foreign_keys", true)?;
 
         Ok(CmdServiceSQL { connection: Rc::new(connection) })
     }
@@ -103,6 +102,116 @@ impl CmdServiceSQL {
     }
 }
 
+/// Builds the `SELECT` for the filters, with every user provided value as a bound parameter
+fn build_search_query(filter: &SearchFilters) -> (String, Vec<Box<dyn ToSql>>) {
+    let mut conditions: Vec<String> = Vec::new();
+    let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+
+    if let Some(id) = filter.id {
+        conditions.push(String::from("id = ?"));
+        values.push(Box::new(id));
+    }
+
+    if let Some(name) = &filter.command {
+        match filter.match_mode {
+            MatchMode::Exact => {
+                conditions.push(String::from("command = ?"));
+                values.push(Box::new(name.clone()));
+            }
+            MatchMode::Prefix => {
+                conditions.push(String::from("command LIKE ? ESCAPE '\\'"));
+                values.push(Box::new(format!("{}%", escape_like(name))));
+            }
+            MatchMode::Substring => {
+                conditions.push(String::from("command LIKE ? ESCAPE '\\'"));
+                values.push(Box::new(format!("%{}%", escape_like(name))));
+            }
+        }
+    }
+
+    if filter.used {
+        conditions.push(String::from("used_times > 0"));
+    }
+
+    if let Some(location) = &filter.location {
+        conditions.push(
+            format!(
+                "id IN (SELECT cmd_id FROM history WHERE {})",
+                location_condition(location, &mut values)
+            )
+        );
+    }
+
+    for tag in &filter.tags {
+        conditions.push(
+            String::from(
+                "id IN (SELECT cmd_tag.cmd_id FROM cmd_tag JOIN tag ON tag.id = cmd_tag.tag_id WHERE tag.name = ?)"
+            )
+        );
+        values.push(Box::new(tag.clone()));
+    }
+
+    let mut sql = format!("SELECT {} FROM cmd", CMD_COLUMNS);
+    if !conditions.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+    }
+
+    if let Some(order) = filter.order {
+        sql.push_str(match order {
+            SearchOrder::UsedTimes => " ORDER BY used_times DESC, id",
+            SearchOrder::LastUsed => " ORDER BY last_used IS NULL, last_used DESC, id",
+            SearchOrder::Command => " ORDER BY command",
+        });
+    }
+
+    // SQLite only accepts OFFSET after a LIMIT, -1 means no limit
+    if filter.limit.is_some() || filter.offset.is_some() {
+        sql.push_str(" LIMIT ? OFFSET ?");
+        values.push(Box::new(filter.limit.map_or(-1, |limit| limit as i64)));
+        values.push(Box::new(filter.offset.unwrap_or(0)));
+    }
+
+    (sql, values)
+}
+
+/// Turns free text into an FTS5 query where every word has to match the start of a token.
+/// Words are quoted so that FTS5 operators and punctuation in them are taken literally.
+fn build_full_text_query(text: &str) -> Option<String> {
+    let terms = text
+        .split_whitespace()
+        .filter(|word| word.chars().any(|c| c.is_alphanumeric()))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+
+    match terms.is_empty() {
+        true => None,
+        false => Some(terms.join(" ")),
+    }
+}
+
+/// Builds the condition on the `history` table that matches executions in the location's
+/// project: the same repository, or the same directory tree outside of repositories.
+fn location_condition(location: &Location, values: &mut Vec<Box<dyn ToSql>>) -> &'static str {
+    match &location.repo_root {
+        Some(root) => {
+            values.push(Box::new(root.clone()));
+            "repo_root = ?"
+        }
+        None => {
+            let cwd = location.cwd.trim_end_matches('/').to_string();
+            values.push(Box::new(cwd.clone()));
+            values.push(Box::new(format!("{}/%", escape_like(&cwd))));
+            "(cwd = ? OR cwd LIKE ? ESCAPE '\\')"
+        }
+    }
+}
+
+/// Escapes `%`, `_` and `\` so the value can be used in a `LIKE ... ESCAPE '\'` clause.
+fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
 impl CmdService for CmdServiceSQL {
     fn add_command(self: &mut Self, command: String) -> Result<(), error::CmdError> {
         self.connection.execute("INSERT INTO cmd (command, used_times) VALUES (?1, ?2)", (
@@ -118,11 +227,16 @@ impl CmdService for CmdServiceSQL {
         command: crate::models::cmd_record::CmdRecord
     ) -> Result<(), crate::error::CmdError> {
         self.connection
-            .execute("UPDATE cmd SET command=?1, used_times=?2 wHERE id = ?3", (
-                &command.command,
-                &command.used_times,
-                &command.id,
-            ))
+            .execute(
+                "UPDATE cmd SET command=?1, used_times=?2, last_used=?3, description=?4 WHERE id = ?5",
+                (
+                    &command.command,
+                    &command.used_times,
+                    &command.last_used,
+                    &command.description,
+                    &command.id,
+                )
+            )
             .map_err(|err| CmdError::SQLError(format!("Could not update: {}", err.to_string())))?;
 
         Ok(())
@@ -153,32 +267,15 @@ impl CmdService for CmdServiceSQL {
         self: &mut Self,
         filter: SearchFilters
     ) -> Vec<crate::models::cmd_record::CmdRecord> {
-        let mut sql = Vec::new();
-        sql.push(String::from("SELECT id, command, used_times FROM cmd"));
-
-        if filter.command.is_some() || filter.used == true {
-            sql.push(String::from(" WHERE "));
-        }
-        if let Some(name) = &filter.command {
-            let s = format!("command LIKE {}", format!("'%{}%'", name));
-            sql.push(s);
-        }
+        let (sql, values) = build_search_query(&filter);
 
-        if filter.command.is_some() && filter.used == true {
-            sql.push(String::from(" AND "));
-        }
-
-        if filter.used {
-            sql.push(String::from("used_times > 0"));
-        }
-
-        log_debug!("SQL: {}", sql.join(" "));
+        log_debug!("SQL: {}", sql);
         self.connection
-            .prepare(sql.join(" ").as_str())
+            .prepare(sql.as_str())
             .expect("Could not build the SQL statement")
-            .query_map([], |row| Ok(CmdRecord::from(row.clone())))
+            .query_map(params_from_iter(values.iter()), |row| Ok(CmdRecord::from(row)))
             .expect("Could not map row")
-            .map(|el| el.unwrap().clone())
+            .map(|el| el.unwrap())
             .collect()
     }
 
@@ -192,8 +289,12 @@ impl CmdService for CmdServiceSQL {
 
     fn insert_command(self: &mut Self, cmd: CmdRecord) -> Result<(), CmdError> {
         self.connection.execute(
-            "INSERT  OR REPLACE INTO cmd (command, used_times) VALUES (?1, ?2)",
-            (&cmd.command, &cmd.used_times)
+            "INSERT INTO cmd (command, used_times, last_used, description) VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(command) DO UPDATE SET
+                used_times = excluded.used_times,
+                last_used = excluded.last_used,
+                description = COALESCE(excluded.description, description)",
+            (&cmd.command, &cmd.used_times, &cmd.last_used, &cmd.description)
         )?;
 
         Ok(())
@@ -228,4 +329,183 @@ impl CmdService for CmdServiceSQL {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn add_history(&mut self, entry: HistoryRecord) -> Result<(), CmdError> {
+        self.connection.execute(
+            "INSERT INTO history (cmd_id, command, cwd, repo_root, exit_code, duration_ms, executed_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            (
+                &entry.cmd_id,
+                &entry.command,
+                &entry.cwd,
+                &entry.repo_root,
+                &entry.exit_code,
+                &entry.duration_ms,
+                &entry.executed_at,
+            )
+        )?;
+
+        Ok(())
+    }
+
+    fn get_history(&mut self, filter: HistoryFilters) -> Result<Vec<HistoryRecord>, CmdError> {
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+
+        if let Some(command) = filter.command {
+            conditions.push("command LIKE ? ESCAPE '\\'");
+            values.push(Box::new(format!("%{}%", escape_like(&command))));
+        }
+
+        if let Some(cwd) = filter.cwd {
+            let cwd = cwd.trim_end_matches('/').to_string();
+            conditions.push("(cwd = ? OR cwd LIKE ? ESCAPE '\\')");
+            values.push(Box::new(cwd.clone()));
+            values.push(Box::new(format!("{}/%", escape_like(&cwd))));
+        }
+
+        if let Some(since) = filter.since {
+            conditions.push("date(executed_at, 'unixepoch', 'localtime') >= date(?)");
+            values.push(Box::new(since));
+        }
+
+        if let Some(until) = filter.until {
+            conditions.push("date(executed_at, 'unixepoch', 'localtime') <= date(?)");
+            values.push(Box::new(until));
+        }
+
+        if filter.failed {
+            conditions.push("(exit_code IS NULL OR exit_code <> 0)");
+        }
+
+        let mut sql = String::from(
+            "SELECT id, cmd_id, command, cwd, repo_root, exit_code, duration_ms, executed_at, datetime(executed_at, 'unixepoch', 'localtime') AS executed_at_local FROM history"
+        );
+
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+
+        sql.push_str(" ORDER BY executed_at DESC, id DESC");
+
+        if let Some(limit) = filter.limit {
+            sql.push_str(" LIMIT ?");
+            values.push(Box::new(limit));
+        }
+
+        log_debug!("SQL: {}", sql);
+        let mut statement = self.connection.prepare(&sql)?;
+        let records = statement
+            .query_map(params_from_iter(values.iter()), |row| Ok(HistoryRecord::from(row)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(records)
+    }
+
+    fn get_location_usage(
+        &mut self,
+        location: &Location
+    ) -> Result<HashMap<usize, usize>, CmdError> {
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+        let sql = format!(
+            "SELECT cmd_id, COUNT(*) FROM history WHERE cmd_id IS NOT NULL AND {} GROUP BY cmd_id",
+            location_condition(location, &mut values)
+        );
+
+        log_debug!("SQL: {}", sql);
+        let mut statement = self.connection.prepare(&sql)?;
+        let usage = statement
+            .query_map(params_from_iter(values.iter()), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<usize, usize>, _>>()?;
+
+        Ok(usage)
+    }
+
+    fn search_full_text(&mut self, text: &str) -> Result<Vec<(CmdRecord, f64)>, CmdError> {
+        let query = match build_full_text_query(text) {
+            Some(query) => query,
+            None => {
+                return Ok(Vec::new());
+            }
+        };
+
+        let sql = format!(
+            "SELECT {}, bm25(cmd_fts) AS rank FROM cmd_fts JOIN cmd ON cmd.id = cmd_fts.rowid WHERE cmd_fts MATCH ?1 ORDER BY rank",
+            CMD_COLUMNS
+        );
+
+        log_debug!("SQL: {} ({})", sql, query);
+        let mut statement = self.connection.prepare(&sql)?;
+        let results = statement
+            .query_map([query], |row| {
+                // bm25 is lower for better matches
+                let rank: f64 = row.get("rank")?;
+                Ok((CmdRecord::from(row), -rank))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(results)
+    }
+
+    fn add_tag(&mut self, cmd_id: usize, tag: &str) -> Result<(), CmdError> {
+        self.connection.execute("INSERT OR IGNORE INTO tag (name) VALUES (?1)", [tag])?;
+        self.connection.execute(
+            "INSERT OR IGNORE INTO cmd_tag (cmd_id, tag_id) SELECT ?1, id FROM tag WHERE name = ?2",
+            (&cmd_id, tag)
+        )?;
+
+        Ok(())
+    }
+
+    fn remove_tag(&mut self, cmd_id: usize, tag: &str) -> Result<(), CmdError> {
+        self.connection.execute(
+            "DELETE FROM cmd_tag WHERE cmd_id = ?1 AND tag_id IN (SELECT id FROM tag WHERE name = ?2)",
+            (&cmd_id, tag)
+        )?;
+        // Tags only exist while some command has them
+        self.connection.execute("DELETE FROM tag WHERE id NOT IN (SELECT tag_id FROM cmd_tag)", [])?;
+
+        Ok(())
+    }
+
+    fn get_tags(&mut self) -> Result<Vec<(String, usize)>, CmdError> {
+        let mut statement = self.connection.prepare(
+            "SELECT tag.name, COUNT(cmd_tag.cmd_id) FROM tag JOIN cmd_tag ON cmd_tag.tag_id = tag.id GROUP BY tag.id ORDER BY tag.name"
+        )?;
+        let tags = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tags)
+    }
+
+    fn add_placeholder_value(
+        &mut self,
+        template: &str,
+        name: &str,
+        value: &str
+    ) -> Result<(), CmdError> {
+        self.connection.execute(
+            "INSERT INTO placeholder_value (template, name, value, used_times, last_used) VALUES (?1, ?2, ?3, 1, ?4)
+            ON CONFLICT (template, name, value) DO UPDATE SET used_times = used_times + 1, last_used = excluded.last_used",
+            (template, name, value, timestamp_now())
+        )?;
+
+        Ok(())
+    }
+
+    fn get_placeholder_values(
+        &mut self,
+        template: &str,
+        name: &str
+    ) -> Result<Vec<String>, CmdError> {
+        let mut statement = self.connection.prepare(
+            "SELECT value FROM placeholder_value WHERE template = ?1 AND name = ?2 ORDER BY used_times DESC, last_used DESC"
+        )?;
+        let values = statement
+            .query_map((template, name), |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+
+        Ok(values)
+    }
+}
@@ -1,20 +1,55 @@
+use std::{ cmp::Ordering, collections::HashMap };
+
 use crate::models::cmd_record::{ CmdRecord, CmdRecordIterable };
+use crate::models::history_record::{ HistoryRecord, timestamp_now };
 use crate::error::CmdError;
-use crate::traits::cmd_service::{ CmdService, SearchFilters, SearchFiltersBuilder };
+use crate::traits::cmd_service::{
+    CmdService,
+    SearchFiltersBuilder,
+    HistoryFilters,
+    MatchMode,
+    SearchOrder,
+};
+use crate::traits::ranking::RankingStrategy;
+use crate::services::location::Location;
+use crate::{ log_debug, log_info };
+
+/// How much more a command that was only ever used in the current project is worth
+const PROJECT_BOOST: f64 = 3.0;
+/// How much the ranking score weighs against the search relevance. A command used ~50 times is
+/// worth about two extra fuzzy matched characters.
+const RANK_WEIGHT: f64 = 10.0;
 
 pub struct Controller<T:CmdService> {
     pub all: T,
     pub used: T,
+    pub ranking: Box<dyn RankingStrategy>,
+    /// Reads work as usual, but nothing is written
+    pub dry_run: bool,
 }
 
 impl<'a, T:CmdService> Controller<T> {
     pub fn get_commands(self: &mut Self, pattern: String) -> Vec<CmdRecord> {
-        return self.all
-            .get_commands(SearchFiltersBuilder::default().command(pattern).build().unwrap());
+        self.get_tagged_commands(pattern, &[])
+    }
+
+    /// Commands matching the pattern that have every one of the tags
+    pub fn get_tagged_commands(&mut self, pattern: String, tags: &[String]) -> Vec<CmdRecord> {
+        self.all.get_commands(
+            SearchFiltersBuilder::default().command(pattern).tags(tags.to_vec()).build().unwrap()
+        )
     }
 
     pub fn get_used_commands(self: &mut Self, pattern: String) -> Vec<CmdRecord> {
-        let mut builder = SearchFiltersBuilder::default();
+        self.get_used_tagged_commands(pattern, &[])
+    }
+
+    pub fn get_used_tagged_commands(
+        &mut self,
+        pattern: String,
+        tags: &[String]
+    ) -> Vec<CmdRecord> {
+        let mut builder = SearchFiltersBuilder::default().tags(tags.to_vec());
         if pattern.len() > 0 {
             builder = builder.command(pattern);
         }
@@ -22,20 +57,198 @@ impl<'a, T:CmdService> Controller<T> {
         return self.used.get_commands(builder.used(true).build().unwrap());
     }
 
+    /// The stored command that is exactly `command`
+    pub fn find_command(&mut self, command: &str) -> Option<CmdRecord> {
+        self.all
+            .get_commands(
+                SearchFiltersBuilder::default()
+                    .command(command.to_string())
+                    .match_mode(MatchMode::Exact)
+                    .build()
+                    .unwrap()
+            )
+            .into_iter()
+            .next()
+    }
+
+    /// Relevance of each command (by id) that matches the text in the full-text index
+    pub fn search_full_text(&mut self, text: &str) -> HashMap<usize, f64> {
+        match self.all.search_full_text(text) {
+            Ok(results) =>
+                results
+                    .into_iter()
+                    .map(|(cmd, relevance)| (cmd.id, relevance))
+                    .collect(),
+            Err(err) => {
+                log_debug!("Full-text search failed: {}", err);
+                HashMap::new()
+            }
+        }
+    }
+
+    /// Used commands matching the pattern, most recently used first. When a location is given,
+    /// only the ones that were executed in its project.
+    pub fn get_recent_commands(
+        &mut self,
+        pattern: String,
+        location: Option<&Location>
+    ) -> Vec<CmdRecord> {
+        let mut builder = SearchFiltersBuilder::default().used(true).order(SearchOrder::LastUsed);
+        if !pattern.is_empty() {
+            builder = builder.command(pattern);
+        }
+        if let Some(location) = location {
+            builder = builder.location(location.clone());
+        }
+
+        self.used.get_commands(builder.build().unwrap())
+    }
+
+    /// Commands matching the pattern that were executed in the location's project
+    pub fn get_project_commands(
+        &mut self,
+        pattern: String,
+        location: &Location,
+        tags: &[String]
+    ) -> Vec<CmdRecord> {
+        let mut builder = SearchFiltersBuilder::default()
+            .location(location.clone())
+            .tags(tags.to_vec());
+        if !pattern.is_empty() {
+            builder = builder.command(pattern);
+        }
+
+        self.used.get_commands(builder.build().unwrap())
+    }
+
+    /// Sorts the commands with the configured ranking strategy, best match first.
+    ///
+    /// When a location is given, commands are boosted by the share of their executions that
+    /// happened in that location's project: up to `1 + PROJECT_BOOST` times their score.
+    pub fn rank(&mut self, commands: &mut Vec<CmdRecord>, location: Option<&Location>) {
+        let mut scored = commands
+            .drain(..)
+            .map(|cmd| (cmd, 0.0))
+            .collect::<Vec<_>>();
+        self.rank_by_relevance(&mut scored, location);
+        commands.extend(scored.into_iter().map(|(cmd, _)| cmd));
+    }
+
+    /// Like `rank`, but each command comes with how relevant it is to the search (e.g. its fuzzy
+    /// match score). Relevance dominates, the ranking score breaks ties between similar matches.
+    pub fn rank_by_relevance(
+        &mut self,
+        commands: &mut [(CmdRecord, f64)],
+        location: Option<&Location>
+    ) {
+        let usage = match location {
+            Some(location) =>
+                self.used.get_location_usage(location).unwrap_or_else(|err| {
+                    log_debug!("Could not get the usage for {:?}: {}", location, err);
+                    HashMap::new()
+                }),
+            None => HashMap::new(),
+        };
+
+        let now = timestamp_now();
+        let score = |(cmd, relevance): &(CmdRecord, f64)| {
+            let local_uses = usage.get(&cmd.id).copied().unwrap_or(0) as f64;
+            let boost = 1.0 + PROJECT_BOOST * local_uses / (std::cmp::max(cmd.used_times, 1) as f64);
+            relevance + RANK_WEIGHT * (self.ranking.score(cmd, now) * boost).ln_1p()
+        };
+
+        commands.sort_by(|cmd1, cmd2| score(cmd2).partial_cmp(&score(cmd1)).unwrap_or(Ordering::Equal));
+    }
+
     pub fn new_command(self: &mut Self, command: String) -> Result<(), CmdError> {
+        if self.skip_write(&format!("adding '{}'", command)) {
+            return Ok(());
+        }
         self.all.add_command(command)
     }
 
+    /// Saves the commands as they are, with their usage. Returns how many were saved.
+    pub fn import_commands(&mut self, commands: Vec<CmdRecord>) -> Result<usize, CmdError> {
+        if self.skip_write(&format!("importing {} commands", commands.len())) {
+            return Ok(0);
+        }
+        let count = commands.len();
+        for command in commands {
+            self.all.insert_command(command)?;
+        }
+        Ok(count)
+    }
+
+    pub fn describe_command(
+        &mut self,
+        mut record: CmdRecord,
+        description: Option<String>
+    ) -> Result<(), CmdError> {
+        if self.skip_write(&format!("describing '{}'", record.command)) {
+            return Ok(());
+        }
+        record.description = description.filter(|d| !d.trim().is_empty());
+        self.all.update_command(record)
+    }
+
+    pub fn add_tag(&mut self, record: &CmdRecord, tag: &str) -> Result<(), CmdError> {
+        let tag = parse_tag(tag)?;
+        if self.skip_write(&format!("tagging '{}' with {}", record.command, tag)) {
+            return Ok(());
+        }
+        self.all.add_tag(record.id, &tag)
+    }
+
+    pub fn remove_tag(&mut self, record: &CmdRecord, tag: &str) -> Result<(), CmdError> {
+        let tag = parse_tag(tag)?;
+        if self.skip_write(&format!("removing {} from '{}'", tag, record.command)) {
+            return Ok(());
+        }
+        self.all.remove_tag(record.id, &tag)
+    }
+
+    /// Every tag with the number of commands that have it
+    pub fn get_tags(&mut self) -> Result<Vec<(String, usize)>, CmdError> {
+        self.all.get_tags()
+    }
+
+    pub fn add_placeholder_value(
+        &mut self,
+        template: &str,
+        name: &str,
+        value: &str
+    ) -> Result<(), CmdError> {
+        if self.skip_write(&format!("remembering {} = '{}'", name, value)) {
+            return Ok(());
+        }
+        self.used.add_placeholder_value(template, name, value)
+    }
+
+    /// Values given before to the placeholder of the template, most used first
+    pub fn get_placeholder_values(&mut self, template: &str, name: &str) -> Vec<String> {
+        self.used.get_placeholder_values(template, name).unwrap_or_else(|err| {
+            log_debug!("Could not get the values of {} for '{}': {}", name, template, err);
+            Vec::new()
+        })
+    }
+
     pub fn add_used_command(
         self: &mut Self,
         mut record: CmdRecord,
         alias: Option<String>
     ) -> Result<(), CmdError> {
+        if self.skip_write(&format!("counting the use of '{}'", record.command)) {
+            return Ok(());
+        }
         let sum = self.used
-            
-            .get_commands(SearchFilters::default())
+            .get_commands(
+                SearchFiltersBuilder::default()
+                    .command(record.command.clone())
+                    .match_mode(MatchMode::Exact)
+                    .build()
+                    .unwrap()
+            )
             .iter()
-            .filter(|cmd| cmd.command == record.command)
             .sum_count();
 
         record.used_times = sum;
@@ -51,6 +264,17 @@ impl<'a, T:CmdService> Controller<T> {
         Ok(())
     }
 
+    pub fn add_history(&mut self, entry: HistoryRecord) -> Result<(), CmdError> {
+        if self.skip_write("recording the execution") {
+            return Ok(());
+        }
+        self.used.add_history(entry)
+    }
+
+    pub fn get_history(&mut self, filter: HistoryFilters) -> Result<Vec<HistoryRecord>, CmdError> {
+        self.used.get_history(filter)
+    }
+
     pub fn get_all_file_path(self: &Self) -> String {
         self.all.get_file_name()
     }
@@ -59,15 +283,38 @@ impl<'a, T:CmdService> Controller<T> {
     }
 
     pub fn clear_files(self: &Self) {
+        if self.skip_write("deleting every command") {
+            return;
+        }
         self.all.clear_commands().expect("Cloud not delete cmd.csv");
         self.used.clear_commands().expect("Cloud not delete cmd_used.csv");
     }
 
     pub fn delete_record(self: &mut Self, cmd: CmdRecord) {
+        if self.skip_write(&format!("deleting '{}'", cmd.command)) {
+            return;
+        }
         self.all.delete_command(cmd).expect("Could not delete record");
     }
 
     pub fn debug(self: &Self) {
         self.all.debug();
     }
-}
\ No newline at end of file
+
+    /// Whether writes are skipped, logging the one that would have happened
+    fn skip_write(&self, action: &str) -> bool {
+        if self.dry_run {
+            log_info!("Dry run, not {}", action);
+        }
+        self.dry_run
+    }
+}
+
+/// Tags are single words, stored lowercase so `K8s` and `k8s` are the same tag
+pub fn parse_tag(tag: &str) -> Result<String, CmdError> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() || tag.contains(|c: char| c.is_whitespace() || c == ',') {
+        return Err(CmdError::InvalidTag(format!("'{}' must be a single word", tag)));
+    }
+    Ok(tag)
+}
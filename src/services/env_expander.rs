@@ -0,0 +1,248 @@
+use std::collections::{ HashMap, HashSet };
+
+use crate::error::CmdError;
+
+/// Expands environment variables in a command the way a POSIX shell would:
+///
+/// - `$VAR` and `${VAR}`. `$HOME_DIR` is the variable `HOME_DIR`, never `$HOME` followed by `_DIR`.
+/// - `${VAR:-default}` uses the default when the variable is unset or empty.
+/// - `${VAR:?message}` fails with the message when the variable is unset or empty.
+/// - Without the colon (`${VAR-default}`, `${VAR?message}`) only unset variables count.
+///
+/// Text in single quotes, escaped dollars (`\$`), special parameters (`$1`, `$?`, ...), command
+/// substitutions (`$(...)`) and other expansions (`${#VAR}`, `${VAR%.txt}`) are left for the
+/// shell, as are the variables the command sets itself before using them (`NAME=value`,
+/// `for NAME in ...`). Variables that are unset and have no default are passed to `ask`, once
+/// per name.
+///
+/// Values are quoted, so that the command is parsed the same once they are in it: `a b; c`
+/// becomes `'a b; c'`, or `a b; c` between double quotes.
+pub fn expand(
+    text: &str,
+    lookup: impl FnMut(&str) -> Option<String>,
+    ask: impl FnMut(&str) -> String
+) -> Result<String, CmdError> {
+    Expander { lookup, ask, asked: HashMap::new(), local: HashSet::new() }.expand(text)
+}
+
+struct Expander<L, A> {
+    lookup: L,
+    ask: A,
+    asked: HashMap<String, String>,
+    /// Variables set by the command itself, so far
+    local: HashSet<String>,
+}
+
+impl<L, A> Expander<L, A> where L: FnMut(&str) -> Option<String>, A: FnMut(&str) -> String {
+    fn expand(&mut self, text: &str) -> Result<String, CmdError> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut result = String::new();
+        let mut in_single = false;
+        let mut in_double = false;
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '\\' if !in_single => {
+                    result.extend(chars[i..std::cmp::min(i + 2, chars.len())].iter());
+                    i += 2;
+                }
+                '\'' if !in_double => {
+                    in_single = !in_single;
+                    result.push('\'');
+                    i += 1;
+                }
+                '"' if !in_single => {
+                    in_double = !in_double;
+                    result.push('"');
+                    i += 1;
+                }
+                '$' if !in_single && chars.get(i + 1) == Some(&'{') => {
+                    match find_closing(&chars, i + 2) {
+                        Some(end) => {
+                            let body: String = chars[i + 2..end].iter().collect();
+                            result.push_str(&self.expand_braced(&body, in_double)?);
+                            i = end + 1;
+                        }
+                        None => {
+                            result.extend(chars[i..].iter());
+                            i = chars.len();
+                        }
+                    }
+                }
+                '$' if !in_single && chars.get(i + 1).is_some_and(|c| is_name_start(*c)) => {
+                    let name: String = chars[i + 1..]
+                        .iter()
+                        .take_while(|c| is_name_char(**c))
+                        .collect();
+                    i += 1 + name.chars().count();
+                    match self.local.contains(&name) {
+                        true => {
+                            result.push('$');
+                            result.push_str(&name);
+                        }
+                        false => {
+                            let value = self.value_or_ask(&name);
+                            result.push_str(&quote(&value, in_double));
+                        }
+                    }
+                }
+                c if !in_single && !in_double && is_name_start(c) && starts_word(&chars, i) => {
+                    self.bind_local(&chars, i);
+                    result.push(c);
+                    i += 1;
+                }
+                c => {
+                    result.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Expands what is between `${` and `}`
+    fn expand_braced(&mut self, body: &str, in_double: bool) -> Result<String, CmdError> {
+        let name: String = match body.chars().next() {
+            Some(c) if is_name_start(c) => body.chars().take_while(|c| is_name_char(*c)).collect(),
+            _ => {
+                return Ok(format!("${{{}}}", body));
+            }
+        };
+        if self.local.contains(&name) {
+            return Ok(format!("${{{}}}", body));
+        }
+        let operation = &body[name.len()..];
+
+        let (check_empty, operation) = match operation.strip_prefix(':') {
+            Some(rest) => (true, rest),
+            None => (false, operation),
+        };
+        let value = self.value(&name);
+        let missing = match &value {
+            None => true,
+            Some(value) => check_empty && value.is_empty(),
+        };
+
+        if operation.is_empty() && !check_empty {
+            let value = self.value_or_ask(&name);
+            return Ok(quote(&value, in_double));
+        }
+
+        if let Some(default) = operation.strip_prefix('-') {
+            return match missing {
+                true => self.expand(default),
+                false => Ok(quote(&value.unwrap_or_default(), in_double)),
+            };
+        }
+
+        if let Some(message) = operation.strip_prefix('?') {
+            return match missing {
+                true => {
+                    let message = match message.is_empty() {
+                        true => "parameter null or not set".to_string(),
+                        false => self.expand(message)?,
+                    };
+                    Err(CmdError::EnvVarError(format!("{}: {}", name, message)))
+                }
+                false => Ok(quote(&value.unwrap_or_default(), in_double)),
+            };
+        }
+
+        // Anything else (`${VAR%.txt}`, `${VAR:0:3}`, ...) is up to the shell
+        Ok(format!("${{{}}}", body))
+    }
+
+    /// Remembers the variable set by the word at `start`, if it is an assignment or a `for` loop
+    fn bind_local(&mut self, chars: &[char], start: usize) {
+        let word: String = chars[start..]
+            .iter()
+            .take_while(|c| is_name_char(**c))
+            .collect();
+        let after = start + word.len();
+
+        if chars.get(after) == Some(&'=') {
+            self.local.insert(word);
+        } else if word == "for" && chars.get(after).is_some_and(|c| c.is_whitespace()) {
+            let name: String = chars[after..]
+                .iter()
+                .skip_while(|c| c.is_whitespace())
+                .take_while(|c| is_name_char(**c))
+                .collect();
+            if !name.is_empty() {
+                self.local.insert(name);
+            }
+        }
+    }
+
+    fn value(&mut self, name: &str) -> Option<String> {
+        (self.lookup)(name).or_else(|| self.asked.get(name).cloned())
+    }
+
+    fn value_or_ask(&mut self, name: &str) -> String {
+        if let Some(value) = self.value(name) {
+            return value;
+        }
+
+        let value = (self.ask)(name);
+        self.asked.insert(name.to_string(), value.clone());
+        value
+    }
+}
+
+/// The value as the shell would read it back: escaped between double quotes, single quoted
+/// elsewhere unless it is plain. An empty value is no word at all, as for an unquoted `$VAR`.
+fn quote(value: &str, in_double: bool) -> String {
+    if in_double {
+        let mut escaped = String::new();
+        for c in value.chars() {
+            if matches!(c, '"' | '\\' | '$' | '`') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        return escaped;
+    }
+
+    let plain = value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-./:,=@%+".contains(c));
+    match plain {
+        true => value.to_string(),
+        false => format!("'{}'", value.replace('\'', "'\\''")),
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Whether a shell word, and so possibly a command or an assignment, starts at `i`
+fn starts_word(chars: &[char], i: usize) -> bool {
+    i == 0 || chars[i - 1].is_whitespace() || matches!(chars[i - 1], ';' | '&' | '|' | '(' | '{')
+}
+
+/// The `}` closing a `${`, skipping over nested ones as in `${A:-${B}}`
+fn find_closing(chars: &[char], from: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in chars.iter().enumerate().skip(from) {
+        match c {
+            '{' => {
+                depth += 1;
+            }
+            '}' if depth == 0 => {
+                return Some(i);
+            }
+            '}' => {
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,55 @@
+use crate::{
+    error::CmdError,
+    models::execution_outcome::ExecutionOutcome,
+    traits::cmd_extension::{ CmdExtension, PlaceholderContext, PlaceholderValue },
+    log_debug,
+};
+
+/// The extensions cmd asks about the selected command, in the order they were registered.
+#[derive(Default)]
+pub struct ExtensionRegistry {
+    extensions: Vec<Box<dyn CmdExtension>>,
+}
+
+impl ExtensionRegistry {
+    pub fn register(&mut self, extension: Box<dyn CmdExtension>) {
+        log_debug!("Registering the {} extension", extension.name());
+        self.extensions.push(extension);
+    }
+
+    /// The extensions whose pattern matches the template
+    fn matching<'a>(&'a self, template: &'a str) -> impl Iterator<Item = &'a Box<dyn CmdExtension>> {
+        self.extensions.iter().filter(move |extension| extension.pattern().is_match(template))
+    }
+
+    /// Values from the first matching extension that has any for the placeholder
+    pub fn placeholder_values(&self, context: &PlaceholderContext) -> Option<Vec<PlaceholderValue>> {
+        self.matching(context.template).find_map(|extension| {
+            let values = extension.placeholder_values(context)?;
+            log_debug!("{} offers {} values", extension.name(), values.len());
+            Some(values)
+        })
+    }
+
+    /// The command as rewritten by the first matching extension that changes it
+    pub fn rewrite(&self, template: &str, command: &str) -> Option<String> {
+        self.matching(template).find_map(|extension| {
+            let rewritten = extension.rewrite(template, command)?;
+            log_debug!("{} rewrote '{}' as '{}'", extension.name(), command, rewritten);
+            Some(rewritten)
+        })
+    }
+
+    /// Lets the first matching extension that wants to run the command do it
+    pub fn execute(
+        &self,
+        template: &str,
+        command: &str
+    ) -> Option<Result<ExecutionOutcome, CmdError>> {
+        self.matching(template).find_map(|extension| {
+            let result = extension.execute(template, command)?;
+            log_debug!("{} ran '{}'", extension.name(), command);
+            Some(result)
+        })
+    }
+}
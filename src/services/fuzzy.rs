@@ -0,0 +1,129 @@
+/// A fuzzy match of a pattern inside a command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Higher is better. Only comparable between matches of the same pattern.
+    pub score: i64,
+    /// Indices (in chars) of the matched characters, sorted
+    pub positions: Vec<usize>,
+}
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CONSECUTIVE: i64 = 6;
+const BONUS_FIRST_CHAR: i64 = 4;
+const PENALTY_GAP: i64 = 1;
+
+/// Matches the pattern as a subsequence of `text`, fzf style.
+///
+/// Every whitespace separated term of the pattern has to match on its own, so `gco main` matches
+/// `git checkout main`. Matching is case insensitive unless the pattern has uppercase letters.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let case_sensitive = pattern.chars().any(|c| c.is_uppercase());
+    let text: Vec<char> = text.chars().collect();
+
+    let mut result = FuzzyMatch::default();
+    for term in pattern.split_whitespace() {
+        let term: Vec<char> = term.chars().collect();
+        let (score, positions) = match_term(&term, &text, case_sensitive)?;
+        result.score += score;
+        result.positions.extend(positions);
+    }
+
+    result.positions.sort_unstable();
+    result.positions.dedup();
+    Some(result)
+}
+
+/// Positions of a plain substring match, for highlighting results that were not fuzzy matched
+pub fn substring_positions(pattern: &str, text: &str) -> Vec<usize> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    if pattern.is_empty() || pattern.len() > text.len() {
+        return Vec::new();
+    }
+
+    (0..=text.len() - pattern.len())
+        .find(|&start| {
+            pattern
+                .iter()
+                .enumerate()
+                .all(|(i, c)| chars_eq(text[start + i], *c, false))
+        })
+        .map(|start| (start..start + pattern.len()).collect())
+        .unwrap_or_default()
+}
+
+fn chars_eq(a: char, b: char, case_sensitive: bool) -> bool {
+    match case_sensitive {
+        true => a == b,
+        false => a.to_lowercase().eq(b.to_lowercase()),
+    }
+}
+
+/// Tries every occurrence of the first char as a starting point and keeps the best scoring one.
+fn match_term(term: &[char], text: &[char], case_sensitive: bool) -> Option<(i64, Vec<usize>)> {
+    let first = *term.first()?;
+    let mut best: Option<(i64, Vec<usize>)> = None;
+
+    for start in 0..text.len() {
+        if !chars_eq(text[start], first, case_sensitive) {
+            continue;
+        }
+
+        let mut positions = vec![start];
+        let mut index = start + 1;
+        for c in &term[1..] {
+            while index < text.len() && !chars_eq(text[index], *c, case_sensitive) {
+                index += 1;
+            }
+            if index == text.len() {
+                break;
+            }
+            positions.push(index);
+            index += 1;
+        }
+
+        if positions.len() < term.len() {
+            // Later starting points cannot find a match if this one did not
+            break;
+        }
+
+        let score = score_positions(&positions, text);
+        if best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+            best = Some((score, positions));
+        }
+    }
+
+    best
+}
+
+fn score_positions(positions: &[usize], text: &[char]) -> i64 {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+
+    for &position in positions {
+        score += SCORE_MATCH;
+
+        let at_boundary = position == 0 || !text[position - 1].is_alphanumeric();
+        if at_boundary {
+            score += BONUS_BOUNDARY;
+        }
+        if position == 0 {
+            score += BONUS_FIRST_CHAR;
+        }
+
+        match previous {
+            Some(prev) if prev + 1 == position => {
+                score += BONUS_CONSECUTIVE;
+            }
+            Some(prev) => {
+                score -= PENALTY_GAP * ((position - prev - 1) as i64);
+            }
+            None => {}
+        }
+        previous = Some(position);
+    }
+
+    score
+}
@@ -1,4 +1,4 @@
-use dialoguer::{ theme::ColorfulTheme, Select, Input, Confirm };
+use dialoguer::{ console::style, theme::ColorfulTheme, Select, MultiSelect, Input, Confirm };
 
 use crate::traits::inputable::Inputable;
 
@@ -16,22 +16,78 @@ impl Inputable for InputManager {
         note
     }
 
+    fn get_input_with_default(&self, prompt: Option<String>, default: String) -> String {
+        let note: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt.unwrap_or(">".into()))
+            .default(default)
+            .interact()
+            .expect("Could not read the input");
+
+        note.replace("\n", "")
+    }
+
+    fn get_optional_input(&self, prompt: Option<String>) -> Option<String> {
+        let note: String = Input::with_theme(&ColorfulTheme::default())
+            .allow_empty(true)
+            .with_prompt(prompt.unwrap_or(">".into()))
+            .interact()
+            .expect("Could not read the input");
+
+        let note = note.replace("\n", "");
+        match note.trim().is_empty() {
+            true => None,
+            false => Some(note),
+        }
+    }
+
     fn select_option(
         self: &Self,
         options: &Vec<String>,
         maybe_prompt: Option<String>
     ) -> Option<usize> {
+        self.select_highlighted(options, &Vec::new(), maybe_prompt)
+    }
+
+    fn select_highlighted(
+        &self,
+        options: &[String],
+        highlights: &[Vec<usize>],
+        maybe_prompt: Option<String>
+    ) -> Option<usize> {
+        let items = options
+            .iter()
+            .enumerate()
+            .map(|(i, option)| {
+                match highlights.get(i) {
+                    Some(positions) if !positions.is_empty() => highlight(option, positions),
+                    _ => option.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+
         Select::with_theme(&ColorfulTheme::default())
             .with_prompt(match maybe_prompt {
                 Some(text) => text,
                 None => "Pick a command".to_string(),
             })
-            .items(&options[..])
+            .items(&items[..])
             .default(0)
             .interact_opt()
             .expect("did not get params")
     }
 
+    fn select_multiple(&self, options: &[String], maybe_prompt: Option<String>) -> Vec<usize> {
+        MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt(match maybe_prompt {
+                Some(text) => text,
+                None => "Pick commands (space to select, enter to confirm)".to_string(),
+            })
+            .items(options)
+            .interact_opt()
+            .expect("did not get params")
+            .unwrap_or_default()
+    }
+
     fn confirm(self: &Self, prompt: String) -> bool {
         let result = Confirm::with_theme(&ColorfulTheme::default())
             .with_prompt(prompt)
@@ -40,4 +96,17 @@ impl Inputable for InputManager {
             .unwrap_or_default();
         result
     }
-}
\ No newline at end of file
+}
+
+fn highlight(option: &str, positions: &[usize]) -> String {
+    option
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            match positions.contains(&i) {
+                true => style(c).yellow().bold().to_string(),
+                false => c.to_string(),
+            }
+        })
+        .collect()
+}
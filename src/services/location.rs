@@ -0,0 +1,41 @@
+use std::{ env, path::Path };
+
+use git2::Repository;
+
+/// Where a command is being run from: the working directory and, if it is inside a git
+/// repository, the root of its working tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    pub cwd: String,
+    pub repo_root: Option<String>,
+}
+
+impl Location {
+    pub fn current() -> Location {
+        match env::current_dir() {
+            Ok(dir) => Location::from_dir(&dir),
+            Err(_) => Location::default(),
+        }
+    }
+
+    pub fn from_dir(dir: &Path) -> Location {
+        let repo_root = Repository::discover(dir)
+            .ok()
+            .and_then(|repo| repo.workdir().map(|workdir| workdir.to_path_buf()))
+            .and_then(|workdir| workdir.canonicalize().ok())
+            .map(|workdir| workdir.to_string_lossy().to_string());
+
+        Location {
+            cwd: dir.to_string_lossy().to_string(),
+            repo_root,
+        }
+    }
+
+    /// The repository root, or the working directory when not inside a repository
+    pub fn project_root(&self) -> &str {
+        match &self.repo_root {
+            Some(root) => root,
+            None => &self.cwd,
+        }
+    }
+}
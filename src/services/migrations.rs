@@ -0,0 +1,275 @@
+use std::path::{ Path, PathBuf };
+
+use rusqlite::Connection;
+
+use crate::{ error::CmdError, services::template, log_debug, log_info };
+
+/// Changes the stored data as a migration needs
+pub type Rewrite = fn(&Connection) -> Result<(), CmdError>;
+
+/// A single, ordered step of the SQLite schema.
+///
+/// Migrations are applied in `version` order and each one is recorded in `PRAGMA user_version`
+/// once it succeeds. Never edit a released migration: add a new one instead.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub sql: &'static str,
+    /// Data changes that SQL alone cannot make, run after `sql` in the same transaction
+    pub rewrite: Option<Rewrite>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Create the cmd table",
+        sql: "
+        CREATE TABLE IF NOT EXISTS cmd (id INTEGER PRIMARY KEY, command TEXT UNIQUE, used_times INTEGER);
+        CREATE INDEX IF NOT EXISTS commands_ind ON cmd (command);
+        ",
+        rewrite: None,
+    },
+    Migration {
+        version: 2,
+        description: "Create the execution history table",
+        sql: "
+        CREATE TABLE IF NOT EXISTS history (
+            id INTEGER PRIMARY KEY,
+            cmd_id INTEGER REFERENCES cmd (id) ON DELETE SET NULL,
+            command TEXT NOT NULL,
+            cwd TEXT NOT NULL,
+            exit_code INTEGER,
+            duration_ms INTEGER NOT NULL,
+            executed_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS history_cmd_ind ON history (cmd_id);
+        CREATE INDEX IF NOT EXISTS history_executed_at_ind ON history (executed_at);
+        ",
+        rewrite: None,
+    },
+    Migration {
+        version: 3,
+        description: "Track when each command was last used",
+        sql: "
+        ALTER TABLE cmd ADD COLUMN last_used INTEGER;
+        UPDATE cmd SET last_used = (SELECT MAX(executed_at) FROM history WHERE history.cmd_id = cmd.id);
+        ",
+        rewrite: None,
+    },
+    Migration {
+        version: 4,
+        description: "Track the repository each execution ran in",
+        sql: "
+        ALTER TABLE history ADD COLUMN repo_root TEXT;
+        CREATE INDEX IF NOT EXISTS history_repo_root_ind ON history (repo_root);
+        ",
+        rewrite: None,
+    },
+    Migration {
+        version: 5,
+        description: "Add a full-text index over commands",
+        sql: "
+        CREATE VIRTUAL TABLE IF NOT EXISTS cmd_fts USING fts5(command);
+        INSERT INTO cmd_fts (rowid, command) SELECT id, command FROM cmd;
+
+        CREATE TRIGGER IF NOT EXISTS cmd_fts_insert AFTER INSERT ON cmd BEGIN
+            INSERT INTO cmd_fts (rowid, command) VALUES (new.id, new.command);
+        END;
+        CREATE TRIGGER IF NOT EXISTS cmd_fts_delete AFTER DELETE ON cmd BEGIN
+            DELETE FROM cmd_fts WHERE rowid = old.id;
+        END;
+        CREATE TRIGGER IF NOT EXISTS cmd_fts_update AFTER UPDATE OF command ON cmd BEGIN
+            UPDATE cmd_fts SET command = new.command WHERE rowid = old.id;
+        END;
+        ",
+        rewrite: None,
+    },
+    Migration {
+        version: 6,
+        description: "Add descriptions and tags to commands",
+        sql: "
+        ALTER TABLE cmd ADD COLUMN description TEXT;
+
+        CREATE TABLE IF NOT EXISTS tag (id INTEGER PRIMARY KEY, name TEXT UNIQUE NOT NULL);
+        CREATE TABLE IF NOT EXISTS cmd_tag (
+            cmd_id INTEGER NOT NULL REFERENCES cmd (id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tag (id) ON DELETE CASCADE,
+            PRIMARY KEY (cmd_id, tag_id)
+        );
+        CREATE INDEX IF NOT EXISTS cmd_tag_tag_ind ON cmd_tag (tag_id);
+
+        DROP TRIGGER IF EXISTS cmd_fts_insert;
+        DROP TRIGGER IF EXISTS cmd_fts_delete;
+        DROP TRIGGER IF EXISTS cmd_fts_update;
+        DROP TABLE IF EXISTS cmd_fts;
+
+        CREATE VIRTUAL TABLE cmd_fts USING fts5(command, description, tags);
+        INSERT INTO cmd_fts (rowid, command, description, tags)
+            SELECT id, command, COALESCE(description, ''), '' FROM cmd;
+
+        CREATE TRIGGER cmd_fts_insert AFTER INSERT ON cmd BEGIN
+            INSERT INTO cmd_fts (rowid, command, description, tags)
+                VALUES (new.id, new.command, COALESCE(new.description, ''), '');
+        END;
+        CREATE TRIGGER cmd_fts_delete AFTER DELETE ON cmd BEGIN
+            DELETE FROM cmd_fts WHERE rowid = old.id;
+        END;
+        CREATE TRIGGER cmd_fts_update AFTER UPDATE OF command, description ON cmd BEGIN
+            UPDATE cmd_fts SET command = new.command, description = COALESCE(new.description, '')
+                WHERE rowid = old.id;
+        END;
+        CREATE TRIGGER cmd_fts_tag_insert AFTER INSERT ON cmd_tag BEGIN
+            UPDATE cmd_fts SET tags = (
+                SELECT group_concat(tag.name, ' ') FROM cmd_tag JOIN tag ON tag.id = cmd_tag.tag_id
+                WHERE cmd_tag.cmd_id = new.cmd_id
+            ) WHERE rowid = new.cmd_id;
+        END;
+        CREATE TRIGGER cmd_fts_tag_delete AFTER DELETE ON cmd_tag BEGIN
+            UPDATE cmd_fts SET tags = COALESCE((
+                SELECT group_concat(tag.name, ' ') FROM cmd_tag JOIN tag ON tag.id = cmd_tag.tag_id
+                WHERE cmd_tag.cmd_id = old.cmd_id
+            ), '') WHERE rowid = old.cmd_id;
+        END;
+        ",
+        rewrite: None,
+    },
+    Migration {
+        version: 7,
+        description: "Remember the values given to placeholders",
+        sql: "
+        CREATE TABLE IF NOT EXISTS placeholder_value (
+            template TEXT NOT NULL,
+            name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            used_times INTEGER NOT NULL,
+            last_used INTEGER NOT NULL,
+            PRIMARY KEY (template, name, value)
+        );
+        ",
+        rewrite: None,
+    },
+    Migration {
+        version: 8,
+        description: "Escape the braces of commands saved before templates",
+        sql: "",
+        rewrite: Some(escape_saved_commands),
+    },
+];
+
+pub fn latest_version() -> u32 {
+    MIGRATIONS.iter()
+        .map(|m| m.version)
+        .max()
+        .unwrap_or(0)
+}
+
+pub fn current_version(connection: &Connection) -> Result<u32, CmdError> {
+    let version: u32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    Ok(version)
+}
+
+/// Brings the database up to `latest_version`.
+///
+/// When `db_path` is set and the database already holds data, a copy is written next to it
+/// (`<db>.v<version>.bak`) before the first pending migration runs. Databases written by a newer
+/// version of cmd are refused.
+pub fn migrate(connection: &Connection, db_path: Option<&Path>) -> Result<u32, CmdError> {
+    let current = current_version(connection)?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(
+            CmdError::MigrationError(
+                format!(
+                    "the database is at schema version {}, but this version of cmd only supports up to {}. Please upgrade cmd.",
+                    current,
+                    latest
+                )
+            )
+        );
+    }
+
+    if current == latest {
+        log_debug!("Schema is up to date (version {})", current);
+        return Ok(current);
+    }
+
+    if let Some(path) = db_path {
+        if has_tables(connection)? {
+            backup_database(connection, &backup_path(path, current))?;
+        }
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        log_debug!("Applying migration {}: {}", migration.version, migration.description);
+
+        let tx = connection.unchecked_transaction()?;
+        tx.execute_batch(migration.sql).map_err(|err|
+            CmdError::MigrationError(
+                format!("migration {} ({}) failed: {}", migration.version, migration.description, err)
+            )
+        )?;
+        if let Some(rewrite) = migration.rewrite {
+            rewrite(&tx).map_err(|err|
+                CmdError::MigrationError(
+                    format!("migration {} ({}) failed: {}", migration.version, migration.description, err)
+                )
+            )?;
+        }
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+
+    Ok(latest)
+}
+
+pub fn backup_path(db_path: &Path, version: u32) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{}.bak", version));
+    db_path.with_file_name(name)
+}
+
+fn has_tables(connection: &Connection) -> Result<bool, CmdError> {
+    let count: usize = connection.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'",
+        [],
+        |row| row.get(0)
+    )?;
+    Ok(count > 0)
+}
+
+fn backup_database(connection: &Connection, path: &Path) -> Result<(), CmdError> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+
+    let target = path
+        .to_str()
+        .ok_or_else(|| CmdError::MigrationError("invalid backup path".to_string()))?;
+
+    log_info!("Backing up the database to {}", target);
+    connection.execute("VACUUM INTO ?1", [target])?;
+    Ok(())
+}
+
+/// `{}` used to be the only placeholder, so any other brace in a saved command was meant for the
+/// shell. Commands that would clash with one saved already are left as they are.
+fn escape_saved_commands(connection: &Connection) -> Result<(), CmdError> {
+    let commands = connection
+        .prepare("SELECT id, command FROM cmd")?
+        .query_map([], |row| Ok((row.get::<usize, usize>(0)?, row.get::<usize, String>(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (id, command) in commands {
+        let escaped = template::escape_saved(&command);
+        if escaped != command {
+            log_debug!("Escaping '{}' as '{}'", command, escaped);
+            connection.execute("UPDATE OR IGNORE cmd SET command = ?1 WHERE id = ?2", (&escaped, id))?;
+            connection.execute(
+                "UPDATE OR IGNORE placeholder_value SET template = ?1 WHERE template = ?2",
+                (&escaped, &command)
+            )?;
+        }
+    }
+    Ok(())
+}
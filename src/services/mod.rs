@@ -1,7 +1,19 @@
 pub mod controller;
 pub mod cmd_service_csv;
 pub mod cmd_service_sql;
+pub mod env_expander;
+pub mod migrations;
 pub mod file_manager;
+pub mod fuzzy;
 pub mod input;
+pub mod location;
 pub mod os_service;
-pub mod cmd_extension_git;
\ No newline at end of file
+pub mod cmd_extension_git;
+pub mod extension_registry;
+pub mod plugin;
+pub mod ranking;
+pub mod template;
+pub mod task_discovery;
+pub mod project_commands;
+pub mod shell_history;
+pub mod suggest;
\ No newline at end of file
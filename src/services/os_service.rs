@@ -1,52 +1,155 @@
-pub struct OSServiceImpl {}
-
-use std::{ fs::OpenOptions, io::{ BufWriter, Write } };
+use std::{
+    io::{ Read, Write },
+    process::{ Command, Stdio },
+    thread,
+    time::{ Duration, Instant },
+};
 
 use mockall::automock;
+use serde::Deserialize;
 
 use crate::{
     traits::os_service::OSService,
-    program::{ parse_programs },
+    program::{ parse_programs, run_programs },
     error::CmdError,
+    models::{ execution_outcome::ExecutionOutcome, history_record::timestamp_now },
+    services::shell_history,
+    traits::history_writer::HistoryWriter,
     log_debug,
+    log_warn,
 };
 
+/// How commands are run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Execution {
+    /// Through `<shell> -c`, so pipes, redirects, globs and the rest of the shell syntax work
+    #[default]
+    Shell,
+    /// Directly, splitting the command on `&&`, `||` and `;` with shell quoting rules
+    Direct,
+}
+
+pub struct OSServiceImpl {
+    pub execution: Execution,
+    /// Used in `Execution::Shell` mode
+    pub shell: String,
+    /// Where executed commands are added to the shell history, if anywhere
+    pub history: Option<Box<dyn HistoryWriter>>,
+}
+
+impl Default for OSServiceImpl {
+    fn default() -> Self {
+        OSServiceImpl { execution: Execution::default(), shell: "sh".to_string(), history: None }
+    }
+}
+
+/// Prints commands instead of running them, for `--dry-run`
+pub struct DryRunOSService {
+    /// Value providers still run through it, as they only list values
+    pub inner: OSServiceImpl,
+}
+
+impl OSService for DryRunOSService {
+    fn execute_command(&self, command: &str, _recorded: &str) -> Result<ExecutionOutcome, CmdError> {
+        println!("{}", command);
+        Ok(ExecutionOutcome { code: Some(0), ..ExecutionOutcome::default() })
+    }
+
+    fn capture_output(&self, command: &str, timeout: Duration) -> Result<String, CmdError> {
+        self.inner.capture_output(command, timeout)
+    }
+}
+
+/// How often `run_with_timeout` checks whether the command finished
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 #[automock]
 impl OSService for OSServiceImpl {
-    fn execute_command(self: &Self, command: &str) -> Result<bool, CmdError> {
-        let programs = parse_programs(&command);
+    fn execute_command(&self, command: &str, recorded: &str) -> Result<ExecutionOutcome, CmdError> {
+        let started_at = timestamp_now();
+        let started = Instant::now();
+        let status = match self.execution {
+            Execution::Shell => {
+                log_debug!("Running '{}' with {}", command, self.shell);
+                Command::new(&self.shell).arg("-c").arg(command).status()?
+            }
+            Execution::Direct => run_programs(parse_programs(command)?)?,
+        };
+        let outcome = ExecutionOutcome::from_status(status, started.elapsed());
 
-        if programs.is_empty() {
-            return Err(CmdError::BaseError("No command to execute".to_string()));
+        if let Some(history) = &self.history {
+            // The command already ran, so a history that cannot be written is not an error
+            if let Err(err) = shell_history::append(history.as_ref(), recorded, started_at, outcome.duration) {
+                log_warn!("Could not add the command to {}: {}", history.path().display(), err);
+            }
         }
 
-        // println!("print -s \"{}\"", command);
-        // let extra = parse_program(&format!("setopt INC_APPEND_HISTORY"));
-        // programs.append(&mut vec![extra]);
-
-        let mut res = None;
-        for mut p in programs {
-            let mut s = p.spawn()?;
-            let w = s.wait()?;
+        Ok(outcome)
+    }
 
-            res = Some(w);
-        }
+    fn capture_output(&self, command: &str, timeout: Duration) -> Result<String, CmdError> {
+        log_debug!("Capturing the output of '{}'", command);
+        let mut process = Command::new(&self.shell);
+        process.arg("-c").arg(command);
+        run_with_timeout(process, command, None, timeout)
+    }
+}
 
-        let r = res.unwrap();
+/// Runs the process with `input` on its stdin and returns what it printed, or an error if it
+/// fails or does not finish in time. `label` names the process in the errors.
+pub fn run_with_timeout(
+    mut process: Command,
+    label: &str,
+    input: Option<Vec<u8>>,
+    timeout: Duration
+) -> Result<String, CmdError> {
+    let mut child = process
+        .stdin(match input {
+            Some(_) => Stdio::piped(),
+            None => Stdio::null(),
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
 
-        let mut home = home::home_dir().expect("Could not find home dir");
-        log_debug!(
-            "Saving settings to: {}/.cmd",
-            home.to_str().expect("could not parse home path")
-        );
+    // Write and read on other threads, or a process with a lot of input or output blocks on a
+    // full pipe
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        thread::spawn(move || {
+            // A process that exits without reading everything is judged by its status
+            let _ = stdin.write_all(&input);
+        });
+    }
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let reader = thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).map(|_| output)
+    });
 
-        home.push(".zsh_history");
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(
+                CmdError::OSProcessError(
+                    format!("'{}' did not finish in {} seconds", label, timeout.as_secs_f32())
+                )
+            );
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
 
-        let f = OpenOptions::new().append(true).open(home)?;
-        let mut w = BufWriter::new(f);
-        w.write_all(command.as_bytes())?;
-        w.flush()?;
+    let output = reader
+        .join()
+        .map_err(|_| CmdError::OSProcessError(format!("Could not read the output of '{}'", label)))??;
 
-        Ok(r.success())
+    match status.success() {
+        true => Ok(output),
+        false => Err(CmdError::OSProcessError(format!("'{}' failed with {}", label, status))),
     }
-}
\ No newline at end of file
+}
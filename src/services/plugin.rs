@@ -0,0 +1,259 @@
+use std::{
+    cell::OnceCell,
+    collections::BTreeMap,
+    ffi::OsStr,
+    os::unix::fs::PermissionsExt,
+    path::{ Path, PathBuf },
+    process::Command,
+    time::Duration,
+};
+
+use regex::Regex;
+use serde::{ de::DeserializeOwned, Deserialize, Serialize };
+
+use crate::{
+    error::CmdError,
+    services::{ os_service::run_with_timeout, shell_history::expand_home },
+    traits::cmd_extension::{ CmdExtension, PlaceholderContext, PlaceholderValue },
+    log_debug,
+    log_warn,
+};
+
+/// Version of the requests and replies below. A plugin that answers the handshake with another
+/// version is not used.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Executables on `$PATH` starting with it are plugins
+pub const PLUGIN_PREFIX: &str = "cmd-plugin-";
+
+/// What cmd writes to the plugin's stdin, as a single JSON object. The plugin replies with a
+/// single JSON object on its stdout and exits; it runs once per request.
+///
+/// - `{"type": "handshake", "protocol": 1}` is sent first, and answered with
+///   `{"protocol": 1, "pattern": "\\bkubectl\\s"}`: the templates the plugin is asked about.
+/// - `{"type": "placeholder", ...}` is answered with
+///   `{"values": [{"value": "api-1", "description": "Running"}]}`, or `{}` to let cmd offer
+///   its own values.
+/// - `{"type": "command", ...}` is sent with the filled command before it runs, and answered with
+///   `{"command": "..."}` to run another command instead, or `{}` to keep it.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PluginRequest<'a> {
+    Handshake {
+        protocol: u32,
+    },
+    Placeholder {
+        protocol: u32,
+        template: &'a str,
+        /// `null` for anonymous `{}` placeholders
+        placeholder: Option<&'a str>,
+        /// The command up to the placeholder
+        before: &'a str,
+        cwd: String,
+        env: BTreeMap<String, String>,
+    },
+    Command {
+        protocol: u32,
+        template: &'a str,
+        command: &'a str,
+        cwd: String,
+        env: BTreeMap<String, String>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct HandshakeReply {
+    protocol: u32,
+    pattern: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PlaceholderReply {
+    values: Option<Vec<ReplyValue>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplyValue {
+    value: String,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CommandReply {
+    command: Option<String>,
+}
+
+/// An external executable that speaks the plugin protocol. The handshake only happens the first
+/// time its pattern is needed, so plugins cost nothing to commands that never fill a template.
+pub struct PluginExtension {
+    pub path: PathBuf,
+    /// How long each request can take before the plugin is killed
+    pub timeout: Duration,
+    name: String,
+    pattern: OnceCell<Regex>,
+}
+
+impl PluginExtension {
+    pub fn new(path: PathBuf, timeout: Duration) -> Self {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string_lossy().to_string());
+        PluginExtension { path, timeout, name, pattern: OnceCell::new() }
+    }
+
+    fn handshake(&self) -> Result<Regex, CmdError> {
+        let reply: Option<HandshakeReply> = self.request(
+            &(PluginRequest::Handshake { protocol: PROTOCOL_VERSION })
+        )?;
+        let reply = reply.ok_or_else(|| CmdError::PluginError(format!("{} did not answer the handshake", self.name)))?;
+
+        if reply.protocol != PROTOCOL_VERSION {
+            return Err(
+                CmdError::PluginError(
+                    format!(
+                        "{} speaks protocol {}, cmd speaks {}",
+                        self.name,
+                        reply.protocol,
+                        PROTOCOL_VERSION
+                    )
+                )
+            );
+        }
+        Regex::new(&reply.pattern).map_err(|err| {
+            CmdError::PluginError(format!("{} has an invalid pattern: {}", self.name, err))
+        })
+    }
+
+    /// Sends the request and parses the reply. An empty reply is `None`.
+    fn request<T: DeserializeOwned>(&self, request: &PluginRequest) -> Result<Option<T>, CmdError> {
+        let input = serde_json::to_vec(request)
+            .map_err(|err| CmdError::PluginError(err.to_string()))?;
+        let label = self.path.to_string_lossy();
+        let output = run_with_timeout(Command::new(&self.path), &label, Some(input), self.timeout)?;
+
+        if output.trim().is_empty() {
+            return Ok(None);
+        }
+        serde_json::from_str(&output)
+            .map(Some)
+            .map_err(|err| CmdError::PluginError(format!("{} replied with invalid JSON: {}", self.name, err)))
+    }
+
+    /// A failing plugin is skipped, so that cmd keeps working without it
+    fn request_or_warn<T: DeserializeOwned>(&self, request: &PluginRequest) -> Option<T> {
+        match self.request(request) {
+            Ok(reply) => reply,
+            Err(err) => {
+                log_warn!("Ignoring the {} plugin: {}", self.name, err);
+                None
+            }
+        }
+    }
+}
+
+impl CmdExtension for PluginExtension {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn pattern(&self) -> &Regex {
+        self.pattern.get_or_init(|| {
+            match self.handshake() {
+                Ok(pattern) => pattern,
+                Err(err) => {
+                    log_warn!("Ignoring the {} plugin: {}", self.name, err);
+                    Regex::new(r"\b\B").expect("Invalid empty pattern")
+                }
+            }
+        })
+    }
+
+    fn placeholder_values(&self, context: &PlaceholderContext) -> Option<Vec<PlaceholderValue>> {
+        let reply: PlaceholderReply = self.request_or_warn(
+            &(PluginRequest::Placeholder {
+                protocol: PROTOCOL_VERSION,
+                template: context.template,
+                placeholder: context.placeholder.name.as_deref(),
+                before: &context.before,
+                cwd: current_dir(),
+                env: std::env::vars().collect(),
+            })
+        )?;
+
+        let values = reply.values?
+            .into_iter()
+            .map(|value| PlaceholderValue { value: value.value, description: value.description })
+            .collect();
+        Some(values)
+    }
+
+    fn rewrite(&self, template: &str, command: &str) -> Option<String> {
+        let reply: CommandReply = self.request_or_warn(
+            &(PluginRequest::Command {
+                protocol: PROTOCOL_VERSION,
+                template,
+                command,
+                cwd: current_dir(),
+                env: std::env::vars().collect(),
+            })
+        )?;
+
+        reply.command.filter(|rewritten| rewritten != command)
+    }
+}
+
+/// The plugins listed in the configuration, then the `cmd-plugin-*` executables on `path`. When
+/// two directories have a plugin with the same name, the first one wins, like for any command.
+pub fn discover(path: Option<&OsStr>, configured: &[String]) -> Vec<PathBuf> {
+    let mut plugins = match home::home_dir() {
+        Some(home) => configured.iter().map(|plugin| expand_home(plugin, &home)).collect::<Vec<_>>(),
+        None => configured.iter().map(PathBuf::from).collect(),
+    };
+
+    let mut names = Vec::new();
+    for dir in path.map(std::env::split_paths).into_iter().flatten() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => {
+                continue;
+            }
+        };
+        let mut found = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| is_plugin(path))
+            .collect::<Vec<_>>();
+        found.sort();
+
+        for plugin in found {
+            let name = plugin.file_name().map(|name| name.to_os_string());
+            if names.contains(&name) || plugins.contains(&plugin) {
+                continue;
+            }
+            log_debug!("Found the plugin {}", plugin.display());
+            names.push(name);
+            plugins.push(plugin);
+        }
+    }
+    plugins
+}
+
+/// An executable file named `cmd-plugin-*`
+fn is_plugin(path: &Path) -> bool {
+    let named = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with(PLUGIN_PREFIX) && name.len() > PLUGIN_PREFIX.len());
+    // Follows symlinks, as package managers link executables into `bin` directories
+    named && std::fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+fn current_dir() -> String {
+    std::env::current_dir()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
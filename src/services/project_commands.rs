@@ -0,0 +1,157 @@
+use std::{ collections::HashMap, fs, path::{ Path, PathBuf } };
+
+use serde::Deserialize;
+
+use crate::{
+    error::CmdError,
+    models::cmd_record::{ CmdRecord, CmdSource },
+    services::controller::parse_tag,
+    log_debug,
+    log_warn,
+};
+
+/// Name of the command files checked into repositories
+pub const PROJECT_FILE: &str = ".cmd.toml";
+
+/// A `.cmd.toml` file, shared by the people working on a project.
+///
+/// ```toml
+/// [[commands]]
+/// command = "kubectl logs -f {pod} -n {namespace:default}"
+/// description = "Follow the logs of a pod"
+/// tags = ["k8s"]
+///
+/// # Commands whose output lines are offered for the placeholders of the commands above. They
+/// # only run once the directory is in `trusted_projects` in `~/.cmd/config.toml`.
+/// [providers]
+/// pod = "kubectl get pods -o name"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProjectFile {
+    #[serde(skip)]
+    pub path: PathBuf,
+    /// Whether the user trusts the file to run its providers
+    #[serde(skip)]
+    pub trusted: bool,
+    pub commands: Vec<ProjectCommand>,
+    pub providers: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProjectCommand {
+    /// A template, like the saved commands
+    pub command: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl ProjectFile {
+    pub fn parse(path: PathBuf, content: &str) -> Result<ProjectFile, CmdError> {
+        let mut file: ProjectFile = toml::from_str(content).map_err(|err| {
+            CmdError::ConfigError(format!("{}: {}", path.display(), err))
+        })?;
+        file.path = path;
+        Ok(file)
+    }
+
+    fn has_command(&self, command: &str) -> bool {
+        self.commands.iter().any(|project_command| project_command.command == command)
+    }
+}
+
+/// The commands of the `.cmd.toml` files in the current directory and the ones above it. They
+/// are offered along with the saved commands, and only stored, to count their use, once they
+/// are run. The files themselves are never written.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectCommands {
+    /// Closest first
+    pub files: Vec<ProjectFile>,
+}
+
+impl ProjectCommands {
+    /// Reads the files from `dir` up to the root. Files that cannot be read are skipped. Only the
+    /// files in the `trusted` directories can run their providers.
+    pub fn load(dir: &Path, trusted: &[PathBuf]) -> ProjectCommands {
+        let trusted = trusted
+            .iter()
+            .map(|dir| fs::canonicalize(dir).unwrap_or_else(|_| dir.clone()))
+            .collect::<Vec<_>>();
+        let mut files = Vec::new();
+        for ancestor in dir.ancestors() {
+            let path = ancestor.join(PROJECT_FILE);
+            if !path.is_file() {
+                continue;
+            }
+            let file = fs::read_to_string(&path)
+                .map_err(CmdError::from)
+                .and_then(|content| ProjectFile::parse(path.clone(), &content));
+            match file {
+                Ok(mut file) => {
+                    log_debug!("Loaded {} commands from {}", file.commands.len(), path.display());
+                    let canonical = fs::canonicalize(ancestor).unwrap_or_else(|_| ancestor.to_path_buf());
+                    file.trusted = trusted.contains(&canonical);
+                    if !file.trusted && !file.providers.is_empty() {
+                        log_warn!(
+                            "Ignoring the providers of {}, add {} to trusted_projects in ~/.cmd/config.toml to run them",
+                            path.display(),
+                            ancestor.display()
+                        );
+                    }
+                    files.push(file);
+                }
+                Err(err) => log_warn!("Ignoring {}: {}", path.display(), err),
+            }
+        }
+        ProjectCommands { files }
+    }
+
+    /// The commands of every file. A command in several files takes its description and tags
+    /// from the closest one.
+    pub fn records(&self) -> Vec<CmdRecord> {
+        let mut records: Vec<CmdRecord> = Vec::new();
+        for file in &self.files {
+            for project_command in &file.commands {
+                if records.iter().any(|record| record.command == project_command.command) {
+                    continue;
+                }
+                let mut tags = project_command.tags
+                    .iter()
+                    .filter_map(|tag| parse_tag(tag).ok())
+                    .collect::<Vec<_>>();
+                tags.sort();
+                tags.dedup();
+
+                records.push(CmdRecord {
+                    command: project_command.command.clone(),
+                    description: project_command.description.clone(),
+                    tags,
+                    source: CmdSource::Project { trusted: file.trusted },
+                    ..CmdRecord::default()
+                });
+            }
+        }
+        records
+    }
+
+    /// The provider of the file that has the command, for the placeholder with that name.
+    /// Providers only apply to the commands of their own file, and only if it is trusted.
+    pub fn provider(&self, command: &str, name: &str) -> Option<String> {
+        self.files
+            .iter()
+            .find(|file| file.has_command(command))
+            .filter(|file| file.trusted)
+            .and_then(|file| file.providers.get(name).cloned())
+    }
+
+    /// Whether the command can run providers: false if it comes from an untrusted file, even
+    /// once it is stored
+    pub fn trusts(&self, command: &str) -> bool {
+        self.files
+            .iter()
+            .find(|file| file.has_command(command))
+            .is_none_or(|file| file.trusted)
+    }
+}
@@ -0,0 +1,61 @@
+use serde::Deserialize;
+
+use crate::{ models::cmd_record::CmdRecord, traits::ranking::RankingStrategy };
+
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+const WEEK: i64 = 7 * DAY;
+
+/// Ranks commands by how many times they were used, regardless of when.
+#[derive(Debug, Default)]
+pub struct UsedTimesRanking {}
+
+impl RankingStrategy for UsedTimesRanking {
+    fn score(&self, record: &CmdRecord, _now: i64) -> f64 {
+        record.used_times as f64
+    }
+}
+
+/// Ranks commands by frequency weighted by how recently they were used, the same way zoxide
+/// ranks directories: uses in the last hour count four times, in the last day twice, in the
+/// last week half and anything older a quarter.
+#[derive(Debug, Default)]
+pub struct FrecencyRanking {}
+
+impl RankingStrategy for FrecencyRanking {
+    fn score(&self, record: &CmdRecord, now: i64) -> f64 {
+        let frequency = record.used_times as f64;
+
+        let weight = match record.last_used {
+            Some(last_used) => {
+                match now - last_used {
+                    age if age < HOUR => 4.0,
+                    age if age < DAY => 2.0,
+                    age if age < WEEK => 0.5,
+                    _ => 0.25,
+                }
+            }
+            None => 0.25,
+        };
+
+        frequency * weight
+    }
+}
+
+/// The ranking strategies that can be selected in the configuration file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ranking {
+    #[default]
+    Frecency,
+    UsedTimes,
+}
+
+impl Ranking {
+    pub fn build(&self) -> Box<dyn RankingStrategy> {
+        match self {
+            Ranking::Frecency => Box::new(FrecencyRanking::default()),
+            Ranking::UsedTimes => Box::new(UsedTimesRanking::default()),
+        }
+    }
+}
@@ -0,0 +1,386 @@
+use std::{
+    fs::{ File, OpenOptions },
+    io::{ Read, Seek, SeekFrom, Write },
+    path::{ Path, PathBuf },
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::{ args::ShellKind, error::CmdError, traits::history_writer::HistoryWriter, log_debug, log_info };
+
+/// How much of the end of a history file is read to detect its format
+const DETECT_BYTES: u64 = 4096;
+
+/// One command per line, optionally preceded by a `#<timestamp>` line as bash writes them when
+/// `HISTTIMEFORMAT` is set
+pub struct BashHistoryWriter {
+    pub path: PathBuf,
+    pub timestamps: bool,
+}
+
+impl HistoryWriter for BashHistoryWriter {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn format(&self, command: &str, started_at: i64, _duration: Duration) -> Vec<u8> {
+        let entry = match self.timestamps {
+            true => format!("#{}\n{}\n", started_at, command),
+            false => format!("{}\n", command),
+        };
+        entry.into_bytes()
+    }
+}
+
+/// zsh's format, plain or with `EXTENDED_HISTORY` (`: <start>:<seconds>;<command>`). Lines of
+/// multiline commands end with a backslash, and bytes zsh uses internally are "metafied" the
+/// way zsh itself does it.
+pub struct ZshHistoryWriter {
+    pub path: PathBuf,
+    pub extended: bool,
+}
+
+impl HistoryWriter for ZshHistoryWriter {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn format(&self, command: &str, started_at: i64, duration: Duration) -> Vec<u8> {
+        let mut entry = match self.extended {
+            true => format!(": {}:{};", started_at, duration.as_secs()).into_bytes(),
+            false => Vec::new(),
+        };
+
+        for byte in command.replace('\n', "\\\n").bytes() {
+            // NUL and 0x83 (Meta) to 0xa2 (Marker) are stored as Meta followed by the byte ^ 32
+            match byte {
+                0 | 0x83..=0xa2 => entry.extend([0x83, byte ^ 32]),
+                _ => entry.push(byte),
+            }
+        }
+        entry.push(b'\n');
+        entry
+    }
+}
+
+/// fish's YAML-like format
+pub struct FishHistoryWriter {
+    pub path: PathBuf,
+}
+
+impl HistoryWriter for FishHistoryWriter {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn format(&self, command: &str, started_at: i64, _duration: Duration) -> Vec<u8> {
+        let escaped = command.replace('\\', "\\\\").replace('\n', "\\n");
+        format!("- cmd: {}\n  when: {}\n", escaped, started_at).into_bytes()
+    }
+}
+
+/// Appends the command to the writer's history file, creating it if needed
+pub fn append(
+    writer: &dyn HistoryWriter,
+    command: &str,
+    started_at: i64,
+    duration: Duration
+) -> Result<(), CmdError> {
+    log_debug!("Adding '{}' to {}", command, writer.path().display());
+    let mut file = OpenOptions::new().create(true).append(true).open(writer.path())?;
+    // A single write, so that it does not interleave with the shell's own appends
+    file.write_all(&writer.format(command, started_at, duration))?;
+    Ok(())
+}
+
+/// The shell history formats that can be selected in the configuration file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShellHistory {
+    /// Picks the format from `$SHELL`, and for bash and zsh from what is already in the file
+    #[default]
+    Auto,
+    Bash,
+    Zsh,
+    ZshExtended,
+    Fish,
+    /// Leaves the shell history alone
+    None,
+}
+
+impl ShellHistory {
+    /// The history format of a shell, given its path as in `$SHELL`
+    pub fn detect(shell: &str) -> ShellHistory {
+        let name = Path::new(shell)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        match name.as_str() {
+            "bash" => ShellHistory::Bash,
+            "zsh" => ShellHistory::Zsh,
+            "fish" => ShellHistory::Fish,
+            _ => ShellHistory::None,
+        }
+    }
+
+    /// The history format and file of the user's shell. `file` overrides where the history is,
+    /// otherwise it is `$HISTFILE` (bash and zsh) or the shell's default file. `None` when the
+    /// history is disabled or the shell has no known format.
+    pub fn locate(&self, file: Option<&str>) -> Option<(ShellHistory, PathBuf)> {
+        let format = match self {
+            ShellHistory::Auto => ShellHistory::detect(&std::env::var("SHELL").unwrap_or_default()),
+            format => *format,
+        };
+        if format == ShellHistory::None {
+            return None;
+        }
+        let home = home::home_dir()?;
+
+        let path = match file {
+            Some(file) => expand_home(file, &home),
+            None => {
+                let histfile = std::env::var("HISTFILE").ok().filter(|file| !file.is_empty());
+                match (format, histfile) {
+                    (ShellHistory::Fish, _) => fish_history_file(&home),
+                    (_, Some(histfile)) => expand_home(&histfile, &home),
+                    (ShellHistory::Bash, None) => home.join(".bash_history"),
+                    (_, None) => zsh_history_file(&home),
+                }
+            }
+        };
+        Some((format, path))
+    }
+
+    /// Builds the writer for the user's shell, writing to the file found by `locate`
+    pub fn build(&self, file: Option<&str>) -> Option<Box<dyn HistoryWriter>> {
+        let (format, path) = match self.locate(file) {
+            Some(found) => found,
+            None => {
+                log_debug!("Not writing to the shell history");
+                return None;
+            }
+        };
+
+        let writer: Box<dyn HistoryWriter> = match format {
+            ShellHistory::Auto | ShellHistory::None => {
+                return None;
+            }
+            ShellHistory::Bash => {
+                let timestamps = *self == ShellHistory::Auto && has_bash_timestamps(&tail(&path));
+                Box::new(BashHistoryWriter { path, timestamps })
+            }
+            ShellHistory::Zsh => {
+                let extended = *self == ShellHistory::Auto && is_zsh_extended(&tail(&path));
+                Box::new(ZshHistoryWriter { path, extended })
+            }
+            ShellHistory::ZshExtended => Box::new(ZshHistoryWriter { path, extended: true }),
+            ShellHistory::Fish => Box::new(FishHistoryWriter { path }),
+        };
+        Some(writer)
+    }
+}
+
+impl From<ShellKind> for ShellHistory {
+    fn from(shell: ShellKind) -> Self {
+        match shell {
+            ShellKind::Zsh => ShellHistory::Zsh,
+            ShellKind::Bash => ShellHistory::Bash,
+            ShellKind::Fish => ShellHistory::Fish,
+        }
+    }
+}
+
+/// A command read back from a shell history file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub command: String,
+    /// Seconds since the UNIX epoch, when the format records it
+    pub timestamp: Option<i64>,
+}
+
+impl ShellHistory {
+    /// Reads the entries of the history file found by `locate`, oldest first
+    pub fn read(&self, file: Option<&str>) -> Result<Vec<HistoryEntry>, CmdError> {
+        let (format, path) = self
+            .locate(file)
+            .ok_or_else(|| CmdError::BaseError("Unknown shell, pass it with --shell".to_string()))?;
+        log_info!("Reading {}", path.display());
+        Ok(format.parse(&std::fs::read(&path)?))
+    }
+
+    /// Reads the entries of a history file in this format. zsh files can be plain or extended.
+    pub fn parse(&self, content: &[u8]) -> Vec<HistoryEntry> {
+        match self {
+            ShellHistory::Bash => parse_bash(&String::from_utf8_lossy(content)),
+            ShellHistory::Zsh | ShellHistory::ZshExtended => parse_zsh(content),
+            ShellHistory::Fish => parse_fish(&String::from_utf8_lossy(content)),
+            ShellHistory::Auto | ShellHistory::None => Vec::new(),
+        }
+    }
+}
+
+fn parse_bash(content: &str) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    let mut timestamp = None;
+
+    for line in content.lines() {
+        if has_bash_timestamps(line) {
+            timestamp = line[1..].parse().ok();
+        } else if !line.trim().is_empty() {
+            entries.push(HistoryEntry { command: line.to_string(), timestamp: timestamp.take() });
+        }
+    }
+    entries
+}
+
+fn parse_zsh(content: &[u8]) -> Vec<HistoryEntry> {
+    let mut bytes = Vec::with_capacity(content.len());
+    let mut iter = content.iter();
+    while let Some(byte) = iter.next() {
+        match (*byte, iter.clone().next()) {
+            (0x83, Some(next)) => {
+                bytes.push(next ^ 32);
+                iter.next();
+            }
+            (byte, _) => bytes.push(byte),
+        }
+    }
+
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    // Set while the previous line ended with a backslash, so this one is part of its command
+    let mut continued = false;
+
+    for line in String::from_utf8_lossy(&bytes).lines() {
+        let (line, continues) = match line.strip_suffix('\\') {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+
+        match entries.last_mut() {
+            Some(entry) if continued => {
+                entry.command.push('\n');
+                entry.command.push_str(line);
+            }
+            _ => {
+                let extended = match is_zsh_extended(line) {
+                    true => line[2..].split_once(';'),
+                    false => None,
+                };
+                entries.push(match extended {
+                    Some((fields, command)) => HistoryEntry {
+                        command: command.to_string(),
+                        timestamp: fields.split(':').next().and_then(|start| start.parse().ok()),
+                    },
+                    None => HistoryEntry { command: line.to_string(), timestamp: None },
+                });
+            }
+        }
+        continued = continues;
+    }
+
+    entries.retain(|entry| !entry.command.trim().is_empty());
+    entries
+}
+
+fn parse_fish(content: &str) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+
+    for line in content.lines() {
+        if let Some(command) = line.strip_prefix("- cmd: ") {
+            entries.push(HistoryEntry { command: unescape_fish(command), timestamp: None });
+        } else if let Some(when) = line.strip_prefix("  when: ") {
+            if let Some(entry) = entries.last_mut() {
+                entry.timestamp = when.trim().parse().ok();
+            }
+        }
+    }
+    entries
+}
+
+fn unescape_fish(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                result.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                result.push('\\');
+                chars.next();
+            }
+            (c, _) => result.push(c),
+        }
+    }
+    result
+}
+
+/// Whether the history uses bash's `#<timestamp>` lines
+pub fn has_bash_timestamps(history: &str) -> bool {
+    history.lines().any(|line| {
+        line.len() > 1 && line.starts_with('#') && line[1..].chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+/// Whether the history was written with zsh's `EXTENDED_HISTORY` option
+pub fn is_zsh_extended(history: &str) -> bool {
+    history.lines().any(|line| {
+        let fields = match line.strip_prefix(": ").and_then(|line| line.split_once(';')) {
+            Some((fields, _)) => fields,
+            None => {
+                return false;
+            }
+        };
+        match fields.split_once(':') {
+            Some((start, duration)) => {
+                !start.is_empty() &&
+                    start.chars().all(|c| c.is_ascii_digit()) &&
+                    !duration.is_empty() &&
+                    duration.chars().all(|c| c.is_ascii_digit())
+            }
+            None => false,
+        }
+    })
+}
+
+/// The last few kilobytes of the file, or nothing if it cannot be read
+fn tail(path: &Path) -> String {
+    let read = || -> std::io::Result<String> {
+        let mut file = File::open(path)?;
+        let length = file.metadata()?.len();
+        file.seek(SeekFrom::Start(length.saturating_sub(DETECT_BYTES)))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        Ok(String::from_utf8_lossy(&bytes).to_string())
+    };
+    read().unwrap_or_default()
+}
+
+/// `path`, with a leading `~/` replaced by the home directory
+pub fn expand_home(path: &str, home: &Path) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+fn zsh_history_file(home: &Path) -> PathBuf {
+    let dir = std::env::var("ZDOTDIR")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.to_path_buf());
+    dir.join(".zsh_history")
+}
+
+fn fish_history_file(home: &Path) -> PathBuf {
+    let data = std::env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".local/share"));
+    data.join("fish").join("fish_history")
+}
@@ -0,0 +1,167 @@
+use std::{ cmp::Reverse, collections::HashMap };
+
+use crate::services::{ shell_history::HistoryEntry, template };
+
+/// Commands at least this long are worth saving even if they were typed once
+pub const LONG_COMMAND: usize = 60;
+/// Commands typed at least this many times are worth saving
+pub const FREQUENT_USES: usize = 3;
+
+/// A command, or a template for several similar ones, that could be saved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// Ready to be saved: literal braces are escaped and variable parts are `{}`
+    pub template: String,
+    /// How many times any of the commands it stands for was typed
+    pub uses: usize,
+    /// The distinct commands it stands for, in the order they were first typed
+    pub examples: Vec<String>,
+}
+
+/// Commands that differ only in a few words, e.g. the same `kubectl logs` for different pods
+struct Group {
+    words: Vec<String>,
+    /// Indexes of the words that are not the same in every command
+    variable: Vec<usize>,
+    uses: usize,
+    examples: Vec<String>,
+}
+
+impl Group {
+    /// Adds the command if it only differs in a few words, which then become variable
+    fn try_add(&mut self, command: &str, words: &[&str], uses: usize) -> bool {
+        if words.len() != self.words.len() || words[0] != self.words[0] {
+            return false;
+        }
+
+        let mut variable = self.variable.clone();
+        for (i, word) in words.iter().enumerate() {
+            if *word != self.words[i] && !variable.contains(&i) {
+                variable.push(i);
+            }
+        }
+        // The program never varies, and at most a third of its arguments can
+        if variable.len() > (words.len() - 1) / 3 {
+            return false;
+        }
+
+        self.variable = variable;
+        self.uses += uses;
+        self.examples.push(command.to_string());
+        true
+    }
+
+    fn into_suggestion(self) -> Suggestion {
+        let template = match self.examples.len() {
+            1 => template::escape(&self.examples[0]),
+            _ => {
+                self.words
+                    .iter()
+                    .enumerate()
+                    .map(|(i, word)| {
+                        match self.variable.contains(&i) {
+                            true => "{}".to_string(),
+                            false => template::escape(word),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+        };
+        Suggestion { template, uses: self.uses, examples: self.examples }
+    }
+}
+
+/// Finds the commands of the history worth saving: long ones, frequent ones, and similar ones
+/// that can become a single template. Commands running an ignored program and the ones
+/// `is_saved` returns true for are left out. Most used first.
+pub fn suggest(
+    entries: &[HistoryEntry],
+    ignore: &[String],
+    is_saved: impl Fn(&str) -> bool
+) -> Vec<Suggestion> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    for entry in entries {
+        let command = entry.command.trim();
+        match positions.get(command) {
+            Some(position) => {
+                counts[*position].1 += 1;
+            }
+            None => {
+                positions.insert(command, counts.len());
+                counts.push((command.to_string(), 1));
+            }
+        }
+    }
+
+    let mut groups: Vec<Group> = Vec::new();
+    for (command, uses) in counts {
+        let words = split_words(&command);
+        let words = words
+            .iter()
+            .map(|word| word.as_str())
+            .collect::<Vec<_>>();
+        let ignored = words.first().is_none_or(|program| ignore.iter().any(|i| i == program));
+        // Multiline commands do not survive being split into words
+        if ignored || command.contains('\n') || is_saved(&command) {
+            continue;
+        }
+
+        if !groups.iter_mut().any(|group| group.try_add(&command, &words, uses)) {
+            groups.push(Group {
+                words: words
+                    .iter()
+                    .map(|word| word.to_string())
+                    .collect(),
+                variable: Vec::new(),
+                uses,
+                examples: vec![command.clone()],
+            });
+        }
+    }
+
+    let mut suggestions = groups
+        .into_iter()
+        .filter(|group| {
+            group.examples.len() > 1 ||
+                group.uses >= FREQUENT_USES ||
+                group.examples[0].chars().count() >= LONG_COMMAND
+        })
+        .map(|group| group.into_suggestion())
+        .filter(|suggestion| !is_saved(&suggestion.template))
+        .collect::<Vec<_>>();
+
+    suggestions.sort_by_key(|suggestion| Reverse(suggestion.uses));
+    suggestions
+}
+
+/// Splits on whitespace, keeping quoted text (quotes included) in a single word
+fn split_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+
+    for c in command.chars() {
+        match (quote, c) {
+            (None, c) if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.push(c);
+            }
+            (Some(q), c) if c == q => {
+                quote = None;
+                word.push(c);
+            }
+            (_, c) => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
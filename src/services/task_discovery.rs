@@ -0,0 +1,511 @@
+use std::{ collections::BTreeMap, fs, path::{ Path, PathBuf } };
+
+use serde::Deserialize;
+
+use crate::{
+    models::cmd_record::{ CmdRecord, CmdSource },
+    services::template,
+    log_debug,
+};
+
+/// How many directories below the current one are searched for build files
+const MAX_DEPTH: usize = 2;
+
+/// Directories that hold dependencies or build output, never tasks of the project
+const SKIPPED_DIRS: [&str; 5] = ["node_modules", "target", "vendor", "dist", "build"];
+
+const MAKEFILES: [&str; 3] = ["GNUmakefile", "makefile", "Makefile"];
+const JUSTFILES: [&str; 3] = ["justfile", "Justfile", ".justfile"];
+
+/// A command found in a build file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    /// A template, run from the directory the search started in
+    pub command: String,
+    /// The build file, and what the task does when it says so
+    pub description: String,
+}
+
+impl Task {
+    fn new(command: String, file: &Path, help: Option<&str>) -> Self {
+        let file = file.to_string_lossy();
+        let description = match help.map(|help| help.trim()).filter(|help| !help.is_empty()) {
+            Some(help) => format!("{}: {}", file, help),
+            None => file.to_string(),
+        };
+        Task { command, description }
+    }
+
+    pub fn record(self) -> CmdRecord {
+        CmdRecord {
+            command: self.command,
+            description: Some(self.description),
+            source: CmdSource::Discovered,
+            ..CmdRecord::default()
+        }
+    }
+}
+
+/// Offers the tasks of the project being worked on: `Makefile` targets, `package.json`
+/// scripts, Cargo binaries, examples and workspace members, and `justfile` recipes.
+#[derive(Debug, Clone, Default)]
+pub struct TaskDiscovery {
+    /// Where the build files are searched, `None` to offer no tasks
+    pub dir: Option<PathBuf>,
+}
+
+impl TaskDiscovery {
+    pub fn new(dir: PathBuf) -> Self {
+        TaskDiscovery { dir: Some(dir) }
+    }
+
+    /// The tasks in the directory and the ones below it, closest first
+    pub fn tasks(&self) -> Vec<CmdRecord> {
+        let mut tasks = Vec::new();
+        if let Some(dir) = &self.dir {
+            walk(dir, PathBuf::new(), 0, &mut tasks);
+        }
+        log_debug!("Discovered {} tasks", tasks.len());
+
+        let mut records: Vec<CmdRecord> = Vec::new();
+        for task in tasks {
+            if !records.iter().any(|record| record.command == task.command) {
+                records.push(task.record());
+            }
+        }
+        records
+    }
+}
+
+fn walk(root: &Path, relative: PathBuf, depth: usize, tasks: &mut Vec<Task>) {
+    let dir = root.join(&relative);
+    tasks.extend(tasks_in(&dir, &relative));
+    if depth >= MAX_DEPTH {
+        return;
+    }
+
+    let mut subdirs = match fs::read_dir(&dir) {
+        Ok(entries) => {
+            entries
+                .flatten()
+                .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|name| !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()))
+                .collect::<Vec<_>>()
+        }
+        Err(_) => Vec::new(),
+    };
+    subdirs.sort();
+
+    for subdir in subdirs {
+        walk(root, relative.join(subdir), depth + 1, tasks);
+    }
+}
+
+/// The tasks of the build files in `dir`, which is `relative` to where the search started
+fn tasks_in(dir: &Path, relative: &Path) -> Vec<Task> {
+    let mut tasks = Vec::new();
+
+    if let Some(name) = MAKEFILES.iter().find(|name| dir.join(name).is_file()) {
+        if let Ok(content) = fs::read_to_string(dir.join(name)) {
+            tasks.extend(make_tasks(&content, relative, name));
+        }
+    }
+    if let Ok(content) = fs::read_to_string(dir.join("package.json")) {
+        tasks.extend(npm_tasks(&content, relative, package_runner(dir)));
+    }
+    if let Ok(content) = fs::read_to_string(dir.join("Cargo.toml")) {
+        tasks.extend(cargo_tasks(&content, dir, relative));
+    }
+    if let Some(name) = JUSTFILES.iter().find(|name| dir.join(name).is_file()) {
+        if let Ok(content) = fs::read_to_string(dir.join(name)) {
+            tasks.extend(just_tasks(&content, relative, name));
+        }
+    }
+
+    tasks
+}
+
+/// `make <target>` for the targets of a Makefile. A `## comment` after the target describes it.
+pub fn make_tasks(content: &str, relative: &Path, file_name: &str) -> Vec<Task> {
+    let file = relative.join(file_name);
+    let mut tasks = Vec::new();
+
+    for line in content.lines() {
+        // Recipe lines, comments and directives like `include` or `ifeq (...)`
+        if line.starts_with(|c: char| c.is_whitespace() || c == '#') {
+            continue;
+        }
+        let (targets, rest) = match line.split_once(':') {
+            Some(split) => split,
+            None => {
+                continue;
+            }
+        };
+        // `VAR := value`, `VAR ::= value` and `VAR = a:b` are variables, not rules
+        if targets.contains('=') || rest.starts_with('=') || rest.starts_with(":=") {
+            continue;
+        }
+        let help = rest.split_once("##").map(|(_, help)| help);
+
+        for target in targets.split_whitespace() {
+            // Special targets like `.PHONY`, pattern rules and computed names
+            let plain = target
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'));
+            if !plain || target.starts_with('.') {
+                continue;
+            }
+
+            let mut command = "make".to_string();
+            if !relative.as_os_str().is_empty() {
+                command.push_str(&format!(" -C {}", quote(&relative.to_string_lossy())));
+            }
+            command.push_str(&format!(" {}", quote(target)));
+            tasks.push(Task::new(template::escape(&command), &file, help));
+        }
+    }
+
+    tasks
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PackageJson {
+    scripts: BTreeMap<String, String>,
+}
+
+/// The package manager the lock file in `dir` belongs to
+fn package_runner(dir: &Path) -> &'static str {
+    let lock_files = [
+        ("pnpm-lock.yaml", "pnpm"),
+        ("yarn.lock", "yarn"),
+        ("bun.lockb", "bun"),
+        ("bun.lock", "bun"),
+    ];
+    lock_files
+        .iter()
+        .find(|(lock_file, _)| dir.join(lock_file).is_file())
+        .map_or("npm", |(_, runner)| runner)
+}
+
+/// `npm run <script>`, or the equivalent for `runner`, for the scripts of a `package.json`.
+/// Scripts run before or after another one (`pretest`, `postbuild`) are left out.
+pub fn npm_tasks(content: &str, relative: &Path, runner: &str) -> Vec<Task> {
+    let package: PackageJson = match serde_json::from_str(content) {
+        Ok(package) => package,
+        Err(err) => {
+            log_debug!("Could not read {}: {}", relative.join("package.json").display(), err);
+            return Vec::new();
+        }
+    };
+    let file = relative.join("package.json");
+
+    let hook = |name: &str| {
+        ["pre", "post"].iter().any(|prefix| {
+            name.strip_prefix(prefix).is_some_and(|script| package.scripts.contains_key(script))
+        })
+    };
+
+    package.scripts
+        .iter()
+        .filter(|(name, _)| !hook(name))
+        .map(|(name, script)| {
+            let mut command = runner.to_string();
+            if !relative.as_os_str().is_empty() {
+                let dir_flag = match runner {
+                    "npm" => "--prefix",
+                    "pnpm" => "--dir",
+                    _ => "--cwd",
+                };
+                command.push_str(&format!(" {} {}", dir_flag, quote(&relative.to_string_lossy())));
+            }
+            command.push_str(&format!(" run {}", quote(name)));
+            Task::new(template::escape(&command), &file, Some(script))
+        })
+        .collect()
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CargoManifest {
+    package: Option<CargoPackage>,
+    workspace: Option<CargoWorkspace>,
+    bin: Vec<CargoTarget>,
+    example: Vec<CargoTarget>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoPackage {
+    name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CargoWorkspace {
+    members: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CargoTarget {
+    name: Option<String>,
+}
+
+/// For the `Cargo.toml` in `dir`: `cargo run --bin` for its binaries, `cargo run --example`
+/// for its examples, and `cargo build -p` and `cargo test -p` for its workspace members.
+/// Binaries and examples are the declared ones and the ones Cargo finds by itself.
+pub fn cargo_tasks(content: &str, dir: &Path, relative: &Path) -> Vec<Task> {
+    let manifest: CargoManifest = match toml::from_str(content) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            log_debug!("Could not read {}: {}", relative.join("Cargo.toml").display(), err);
+            return Vec::new();
+        }
+    };
+    let file = relative.join("Cargo.toml");
+    let cargo = |subcommand: &str, args: &str| {
+        let command = match relative.as_os_str().is_empty() {
+            true => format!("cargo {} {}", subcommand, args),
+            false => format!("cargo {} --manifest-path {} {}", subcommand, quote(&file.to_string_lossy()), args),
+        };
+        template::escape(&command)
+    };
+    let mut tasks = Vec::new();
+
+    if let Some(package) = &manifest.package {
+        let mut bins = declared(&manifest.bin);
+        if dir.join("src/main.rs").is_file() {
+            bins.push(package.name.clone());
+        }
+        bins.extend(target_files(&dir.join("src/bin")));
+        let mut examples = declared(&manifest.example);
+        examples.extend(target_files(&dir.join("examples")));
+
+        for (kind, names) in [("--bin", dedup(bins)), ("--example", dedup(examples))] {
+            for name in names {
+                tasks.push(Task::new(cargo("run", &format!("{} {}", kind, quote(&name))), &file, None));
+            }
+        }
+    }
+
+    if let Some(workspace) = &manifest.workspace {
+        for member in workspace_members(dir, &workspace.members) {
+            for subcommand in ["build", "test"] {
+                tasks.push(Task::new(cargo(subcommand, &format!("-p {}", quote(&member))), &file, None));
+            }
+        }
+    }
+
+    tasks
+}
+
+fn declared(targets: &[CargoTarget]) -> Vec<String> {
+    targets
+        .iter()
+        .filter_map(|target| target.name.clone())
+        .collect()
+}
+
+/// `name` for each `name.rs` and `name/main.rs` in `dir`
+fn target_files(dir: &Path) -> Vec<String> {
+    let mut names = match fs::read_dir(dir) {
+        Ok(entries) => {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter_map(|path| {
+                    match path.is_dir() {
+                        true if path.join("main.rs").is_file() => path.file_name().map(|name| name.to_os_string()),
+                        true => None,
+                        false if path.extension().is_some_and(|extension| extension == "rs") => {
+                            path.file_stem().map(|name| name.to_os_string())
+                        }
+                        false => None,
+                    }
+                })
+                .map(|name| name.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        }
+        Err(_) => Vec::new(),
+    };
+    names.sort();
+    names
+}
+
+/// Package names of the workspace members. Members can end in `*` to take every directory.
+fn workspace_members(dir: &Path, members: &[String]) -> Vec<String> {
+    let mut member_dirs = Vec::new();
+    for member in members {
+        match member.strip_suffix('*') {
+            Some(parent) => {
+                let mut found = fs::read_dir(dir.join(parent))
+                    .map(|entries| {
+                        entries
+                            .flatten()
+                            .map(|entry| entry.path())
+                            .filter(|path| path.is_dir())
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                found.sort();
+                member_dirs.extend(found);
+            }
+            None => member_dirs.push(dir.join(member)),
+        }
+    }
+
+    let names = member_dirs
+        .iter()
+        .filter_map(|member| {
+            let content = fs::read_to_string(member.join("Cargo.toml")).ok()?;
+            let manifest: CargoManifest = toml::from_str(&content).ok()?;
+            manifest.package.map(|package| package.name)
+        })
+        .collect();
+    dedup(names)
+}
+
+/// `just <recipe>` for the public recipes of a justfile. The comment above a recipe describes
+/// it, and its parameters become placeholders: `deploy env='dev':` is `just deploy {env:dev}`.
+pub fn just_tasks(content: &str, relative: &Path, file_name: &str) -> Vec<Task> {
+    let file = relative.join(file_name);
+    let mut tasks = Vec::new();
+    let mut comment: Option<&str> = None;
+    let mut private = false;
+
+    for line in content.lines() {
+        if let Some(text) = line.strip_prefix('#') {
+            comment = Some(text);
+            continue;
+        }
+        if line.starts_with('[') {
+            private = private || line.contains("private");
+            continue;
+        }
+        let recipe = match line.starts_with(|c: char| !c.is_whitespace()) {
+            true => parse_recipe(line),
+            false => None,
+        };
+        let (doc, is_private) = (comment.take(), std::mem::take(&mut private));
+
+        let (name, params) = match recipe {
+            Some(recipe) => recipe,
+            None => {
+                continue;
+            }
+        };
+        if is_private || name.starts_with('_') {
+            continue;
+        }
+
+        let mut command = "just".to_string();
+        if !relative.as_os_str().is_empty() {
+            command.push_str(&format!(" --justfile {}", quote(&file.to_string_lossy())));
+        }
+        command = format!("{} {}", template::escape(&command), name);
+        for param in params {
+            command.push(' ');
+            command.push_str(&param);
+        }
+        tasks.push(Task::new(command, &file, doc));
+    }
+
+    tasks
+}
+
+/// The name of the recipe on the line and its parameters as placeholders, or `None` when the
+/// line is something else, like a variable or a setting
+fn parse_recipe(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.strip_prefix('@').unwrap_or(line);
+    let (head, rest) = line.split_once(':')?;
+    // `name := value`, `set shell := [...]`, `alias b := build`
+    if rest.starts_with('=') {
+        return None;
+    }
+
+    let mut words = split_params(head).into_iter();
+    let name = words.next()?;
+    let valid = name
+        .chars()
+        .enumerate()
+        .all(|(i, c)| c.is_ascii_alphanumeric() || c == '_' || (i > 0 && c == '-'));
+    if !valid || name.chars().next().is_none_or(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let params = words
+        .map(|param| {
+            // `+args` takes one or more values, `*args` zero or more, `$name` is exported
+            let optional = param.starts_with('*');
+            let param = param.trim_start_matches(['+', '*', '$']);
+            match param.split_once('=') {
+                Some((name, default)) => {
+                    match unquote(default) {
+                        Some(default) => format!("{{{}:{}}}", name, default),
+                        // An expression only just can evaluate
+                        None => format!("{{{}}}", name),
+                    }
+                }
+                None if optional => format!("{{{}:}}", param),
+                None => format!("{{{}}}", param),
+            }
+        })
+        .collect();
+
+    Some((name, params))
+}
+
+/// The words of a recipe header, keeping quoted defaults with spaces in one word
+fn split_params(head: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+
+    for c in head.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => {
+                quote = None;
+                word.push(c);
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.push(c);
+            }
+            (None, c) if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            (_, c) => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn unquote(value: &str) -> Option<&str> {
+    ['\'', '"'].iter().find_map(|q| value.strip_prefix(*q)?.strip_suffix(*q))
+}
+
+fn dedup(names: Vec<String>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for name in names {
+        if !unique.contains(&name) {
+            unique.push(name);
+        }
+    }
+    unique
+}
+
+/// The word, in single quotes when the shell would split or expand it
+fn quote(word: &str) -> String {
+    let plain = !word.is_empty() &&
+        word.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ':' | '@' | '+' | '=' | ','));
+    match plain {
+        true => word.to_string(),
+        false => format!("'{}'", word.replace('\'', "'\\''")),
+    }
+}
@@ -0,0 +1,307 @@
+/// A `{...}` slot of a command template that the user fills before running it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Placeholder {
+    /// `None` for anonymous `{}` placeholders, which are filled one by one
+    pub name: Option<String>,
+    /// Value used when the user does not type anything: `{port:8080}`
+    pub default: Option<String>,
+    /// The only values allowed, when set: `{env:dev|staging|prod}`
+    pub choices: Vec<String>,
+    /// Command whose output lines are offered as values: `{pod:$(kubectl get pods -o name)}`
+    pub provider: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    /// Index in `Template::placeholders`
+    Slot(usize),
+}
+
+/// A parsed command template.
+///
+/// - `{}` is an anonymous placeholder, every occurrence is filled separately.
+/// - `{branch}` is a named placeholder, filled once and substituted everywhere it appears.
+/// - `{port:8080}` has a default value.
+/// - `{env:dev|staging|prod}` can only be one of the listed values.
+/// - `{pod:$(kubectl get pods -o name)}` offers the lines printed by the command.
+/// - `{{` and `}}` are literal braces, e.g. `find . -exec rm {{}} \;`.
+/// - `${...}` is left alone for the shell, as is any brace group that is not a valid
+///   placeholder (`awk '{print $1}'`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Template {
+    segments: Vec<Segment>,
+    /// Unique placeholders, in order of first appearance
+    pub placeholders: Vec<Placeholder>,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Template {
+        let chars: Vec<char> = text.chars().collect();
+        let mut template = Template::default();
+        let mut literal = String::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '{' | '}' if chars.get(i + 1) == Some(&chars[i]) => {
+                    literal.push(chars[i]);
+                    i += 2;
+                }
+                '$' if chars.get(i + 1) == Some(&'{') => {
+                    let end = find_closing(&chars, i + 2).unwrap_or(chars.len() - 1);
+                    literal.extend(&chars[i..=end]);
+                    i = end + 1;
+                }
+                '{' => {
+                    let parsed = find_closing(&chars, i + 1).and_then(|end| {
+                        let body: String = chars[i + 1..end].iter().collect();
+                        parse_placeholder(&body).map(|placeholder| (placeholder, end))
+                    });
+
+                    match parsed {
+                        Some((placeholder, end)) => {
+                            if !literal.is_empty() {
+                                template.segments.push(Segment::Text(std::mem::take(&mut literal)));
+                            }
+                            let slot = template.add_placeholder(placeholder);
+                            template.segments.push(Segment::Slot(slot));
+                            i = end + 1;
+                        }
+                        None => {
+                            literal.push('{');
+                            i += 1;
+                        }
+                    }
+                }
+                c => {
+                    literal.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        if !literal.is_empty() {
+            template.segments.push(Segment::Text(literal));
+        }
+        template
+    }
+
+    pub fn has_placeholders(&self) -> bool {
+        !self.placeholders.is_empty()
+    }
+
+    /// Builds the command, with `values[i]` in place of `placeholders[i]`. Missing values fall
+    /// back to the placeholder's default, or to nothing.
+    pub fn render(&self, values: &[String]) -> String {
+        self.segments
+            .iter()
+            .map(|segment| {
+                match segment {
+                    Segment::Text(text) => text.clone(),
+                    Segment::Slot(slot) =>
+                        values
+                            .get(*slot)
+                            .cloned()
+                            .or_else(|| self.placeholders[*slot].default.clone())
+                            .unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+
+    /// The command up to the first occurrence of the placeholder, with the placeholders before
+    /// it left as `{}`
+    pub fn text_before(&self, slot: usize) -> String {
+        let mut text = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(literal) => text.push_str(literal),
+                Segment::Slot(other) if *other == slot => {
+                    break;
+                }
+                Segment::Slot(_) => text.push_str("{}"),
+            }
+        }
+        text
+    }
+
+    fn add_placeholder(&mut self, placeholder: Placeholder) -> usize {
+        if placeholder.name.is_some() {
+            let existing = self.placeholders.iter().position(|p| p.name == placeholder.name);
+            if let Some(slot) = existing {
+                // The first default wins, but a later occurrence can still provide one
+                let existing = &mut self.placeholders[slot];
+                if existing.default.is_none() {
+                    existing.default = placeholder.default;
+                }
+                if existing.choices.is_empty() {
+                    existing.choices = placeholder.choices;
+                }
+                if existing.provider.is_none() {
+                    existing.provider = placeholder.provider;
+                }
+                return slot;
+            }
+        }
+
+        self.placeholders.push(placeholder);
+        self.placeholders.len() - 1
+    }
+}
+
+/// Escapes braces so that `Template::parse(&escape(text)).render(&[])` is `text` again.
+/// `${...}` is kept as is, since templates never treat it as a placeholder.
+pub fn escape(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut escaped = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '$' if chars.get(i + 1) == Some(&'{') => {
+                let end = find_closing(&chars, i + 2).unwrap_or(chars.len() - 1);
+                escaped.extend(&chars[i..=end]);
+                i = end + 1;
+            }
+            c @ ('{' | '}') => {
+                escaped.push(c);
+                escaped.push(c);
+                i += 1;
+            }
+            c => {
+                escaped.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    escaped
+}
+
+/// Escapes a command saved when `{}` was the only placeholder, so that it means the same as a
+/// template: `awk '{print}' {}` becomes `awk '{{print}}' {}`.
+pub fn escape_saved(command: &str) -> String {
+    command
+        .split("{}")
+        .map(escape)
+        .collect::<Vec<_>>()
+        .join("{}")
+}
+
+/// Index of the `}` closing a brace group whose content starts at `from`. Braces in quotes and
+/// in `$(...)` do not count, as in `{x:$(awk '{print $1}' file)}`.
+fn find_closing(chars: &[char], from: usize) -> Option<usize> {
+    let mut i = from;
+    while i < chars.len() {
+        match chars[i] {
+            '}' => {
+                return Some(i);
+            }
+            '\'' | '"' => {
+                i = skip_quoted(chars, i);
+            }
+            '$' if chars.get(i + 1) == Some(&'(') => {
+                i = skip_substitution(chars, i + 2);
+            }
+            _ => {
+                i += 1;
+            }
+        }
+    }
+    None
+}
+
+/// Index after the quote closing the one at `start`. A quote that is never closed is taken as
+/// a plain character.
+fn skip_quoted(chars: &[char], start: usize) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            c if c == quote => {
+                return i + 1;
+            }
+            // Only double quotes have escapes
+            '\\' if quote == '"' => {
+                i += 2;
+            }
+            _ => {
+                i += 1;
+            }
+        }
+    }
+    start + 1
+}
+
+/// Index after the `)` closing a `$(` whose content starts at `from`. A substitution that is
+/// never closed is taken as plain text.
+fn skip_substitution(chars: &[char], from: usize) -> usize {
+    let mut depth = 1;
+    let mut i = from;
+    while i < chars.len() {
+        match chars[i] {
+            '\'' | '"' => {
+                i = skip_quoted(chars, i);
+                continue;
+            }
+            '(' => {
+                depth += 1;
+            }
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    from
+}
+
+/// Parses what is between the braces: an optional name, optionally followed by `:default`,
+/// `:choice|choice|...` or `:$(command)`
+fn parse_placeholder(body: &str) -> Option<Placeholder> {
+    let (name, value) = match body.split_once(':') {
+        Some((name, value)) => (name, Some(value)),
+        None => (body, None),
+    };
+
+    let provider = value
+        .filter(|value| value.starts_with("$(") && value.ends_with(')'))
+        .map(|value| value[2..value.len() - 1].trim().to_string());
+
+    let (default, choices) = match value {
+        _ if provider.is_some() => (None, Vec::new()),
+        Some(value) if value.contains('|') => {
+            let choices = value
+                .split('|')
+                .map(|choice| choice.trim().to_string())
+                .filter(|choice| !choice.is_empty())
+                .collect::<Vec<_>>();
+            (None, choices)
+        }
+        Some(value) => (Some(value.to_string()), Vec::new()),
+        None => (None, Vec::new()),
+    };
+
+    let valid_name = name
+        .chars()
+        .enumerate()
+        .all(|(i, c)| c.is_alphanumeric() || c == '_' || (i > 0 && c == '-'));
+    if !valid_name {
+        return None;
+    }
+
+    Some(Placeholder {
+        name: match name.is_empty() {
+            true => None,
+            false => Some(name.to_string()),
+        },
+        default,
+        choices,
+        provider,
+    })
+}
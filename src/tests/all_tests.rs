@@ -4,17 +4,27 @@ use std::cell::RefCell;
 use std::collections::{ HashMap, HashSet };
 use std::io::{ BufWriter, Cursor, Write, Read };
 use std::rc::Rc;
-use crate::args::{ Cli, Commands };
+use std::time::Duration;
+use crate::args::{ Cli, Commands, ShellKind };
 use crate::cmd::cmd_add::AddHandler;
 use crate::cmd::cmd_get::GetHandler;
+use crate::cmd::cmd_import::ImportHandler;
+use crate::cmd::cmd_suggest::{ SuggestHandler, SuggestQuery };
 use crate::cmd_csv::{ read_cmd_file };
 use crate::models::cmd_record::{ CmdRecord, CmdRecordIterable };
+use crate::models::execution_outcome::ExecutionOutcome;
 use crate::error::CmdError;
 use crate::services::cmd_service_sql::CmdServiceSQL;
 use crate::services::controller::Controller;
+use crate::services::extension_registry::ExtensionRegistry;
+use crate::services::task_discovery::TaskDiscovery;
+use crate::services::project_commands::ProjectCommands;
+use crate::services::template::Template;
+use crate::services::ranking::Ranking;
+use crate::config::Config;
 use crate::services::file_manager::{ FileManagerImpl, build_file_manager };
-use crate::services::os_service::MockOSServiceImpl;
-use crate::traits::cmd_service::CmdService;
+use crate::services::os_service::{ DryRunOSService, MockOSServiceImpl, OSServiceImpl };
+use crate::traits::cmd_service::{ CmdService, HistoryFilters };
 use crate::traits::inputable::{ MockInputable };
 
 use crate::{ FileManager, Deps, log_info, log_debug, log_error };
@@ -34,6 +44,20 @@ fn get_cursor(records: &mut Vec<&str>) -> Cursor<Vec<u8>> {
     Cursor::new(buf1)
 }
 
+/// Collects what a handler writes, for the test to read afterwards
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 fn get_deps<'a>(
     mock_opts: MutRef<MockOpts<'static>>,
     all: Vec<&str>
@@ -43,6 +67,9 @@ fn get_deps<'a>(
         command: Some(Commands::Add { pattern: false, execute: false }),
         verbose: true,
         dry_run: false,
+        here: false,
+        substring: false,
+        print: false,
         generator: None,
     };
     get_deps_2(mock_opts, args, all)
@@ -72,10 +99,26 @@ fn get_deps_2<'a>(
     let controller = Controller {
         all: all_cmd_service.clone(),
         used: all_cmd_service,
+        ranking: Ranking::default().build(),
+        dry_run: args.dry_run,
     };
 
     let mut mock_input = MockInputable::new();
     mock_input.expect_get_input().returning(|_| "git".to_string());
+    mock_input.expect_get_optional_input().returning(|_| None);
+    mock_input.expect_get_input_with_default().returning(|_, default| default);
+    mock_input.expect_select_multiple().returning(|opts, _| (0..opts.len()).collect());
+
+    let highlighted_opts = Rc::clone(&mock_opts);
+    mock_input.expect_select_highlighted().returning_st(move |opts, _highlights, _maybe_prompt| {
+        let x = highlighted_opts.as_ref();
+        let opts = opts.to_vec();
+
+        let result = x.borrow_mut().get_selected_record(&opts);
+        x.borrow_mut().capture_options_for_command(opts.clone());
+
+        Some(std::cmp::min(result, opts.len() - 1))
+    });
 
     mock_input.expect_select_option().returning_st(move |opts, _maybe_prompt| {
         let prompt = match _maybe_prompt {
@@ -102,12 +145,21 @@ fn get_deps_2<'a>(
     });
 
     let mut mock_os = MockOSServiceImpl::new();
-    mock_os.expect_execute_command().returning_st(|arg| {
+    mock_os.expect_execute_command().returning_st(|arg, _| {
         log_info!("Running command {}", arg);
-        Ok(true)
+        Ok(ExecutionOutcome { code: Some(0), ..ExecutionOutcome::default() })
     });
 
-    Ok(Deps { args, controller, input: Rc::new(mock_input), os: Rc::new(mock_os) })
+    Ok(Deps {
+        args,
+        config: Config::default(),
+        controller,
+        input: Rc::new(mock_input),
+        os: Rc::new(mock_os),
+        extensions: ExtensionRegistry::default(),
+        tasks: TaskDiscovery::default(),
+        project: ProjectCommands::default(),
+    })
 }
 
 #[test]
@@ -118,7 +170,28 @@ fn it_works() -> Result<(), Box<dyn std::error::Error>> {
     let mock_opts = MockOpts::new();
 
     let deps = get_deps(mock_opts, all_records)?;
-    crate::app(deps);
+    assert_eq!(crate::app(deps), 0);
+
+    Ok(())
+}
+
+#[test]
+fn print_mode_writes_only_the_command_to_stdout() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec!["1,git commit -m {},0"];
+
+    let args = Cli {
+        command: Some(Commands::Get { pattern: Some("commit".to_string()), tags: Vec::new() }),
+        print: true,
+        ..Cli::default()
+    };
+    let deps = get_deps_2(MockOpts::new(), args, all_records)?;
+
+    let output = SharedOutput::default();
+    assert_eq!(crate::app_with_output(deps, Box::new(output.clone())), 0);
+
+    // No log lines around it, for the shell widget to insert it as it is
+    assert_eq!(String::from_utf8(output.0.take()).unwrap(), "git commit -m git\n");
 
     Ok(())
 }
@@ -137,6 +210,338 @@ fn add_command_test() -> Result<(), CmdError> {
     result
 }
 
+#[test]
+fn dry_run_does_not_execute_or_save() -> Result<(), Box<dyn std::error::Error>> {
+    initialize();
+    let dir = tempfile::tempdir()?;
+    let marker = dir.path().join("marker");
+    let template = format!("1,touch {} {{}},0", marker.display());
+
+    let args = Cli {
+        command: Some(Commands::Get { pattern: Some("touch".to_string()), tags: Vec::new() }),
+        dry_run: true,
+        ..Cli::default()
+    };
+    let mut deps = get_deps_2(MockOpts::new(), args, vec![template.as_str()])?;
+    deps.os = Rc::new(DryRunOSService { inner: OSServiceImpl::default() });
+
+    let deps_ref = Rc::new(RefCell::new(deps));
+    GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("touch".to_string()))?;
+    AddHandler::new(Rc::clone(&deps_ref)).add_command(false, true)?;
+
+    assert!(!marker.exists());
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    assert!(controller.get_history(HistoryFilters::default())?.is_empty());
+    assert!(controller.find_command("git").is_none());
+    let commands = controller.get_commands(String::new());
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands.first().unwrap().used_times, 0);
+    assert!(controller.get_placeholder_values(&commands.first().unwrap().command, "#1").is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn print_mode_counts_the_command_without_running_it() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec!["1,git commit -m {},0"];
+
+    let args = Cli {
+        command: Some(Commands::Get { pattern: Some("commit".to_string()), tags: Vec::new() }),
+        print: true,
+        ..Cli::default()
+    };
+    let mut deps = get_deps_2(MockOpts::new(), args, all_records)?;
+    let mut mock_os = MockOSServiceImpl::new();
+    mock_os.expect_execute_command().never();
+    deps.os = Rc::new(mock_os);
+
+    let deps_ref = Rc::new(RefCell::new(deps));
+    let output = SharedOutput::default();
+    GetHandler::new(Rc::clone(&deps_ref))
+        .with_output(Box::new(output.clone()))
+        .get_command(&Some("commit".to_string()))?;
+
+    // Only the command, for the shell widget to insert
+    assert_eq!(String::from_utf8(output.0.take()).unwrap(), "git commit -m git\n");
+
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    assert!(controller.get_history(HistoryFilters::default())?.is_empty());
+    let used = controller.get_used_commands(String::new());
+    assert_eq!(
+        used
+            .iter()
+            .map(|cmd| cmd.command.as_str())
+            .collect::<Vec<_>>(),
+        vec!["git commit -m {}", "git commit -m git"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn print_mode_leaves_variables_to_the_shell() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec!["1,echo $CMD_TEST_UNSET_PRINT ${HOME},0"];
+
+    let args = Cli {
+        command: Some(Commands::Get { pattern: Some("echo".to_string()), tags: Vec::new() }),
+        print: true,
+        ..Cli::default()
+    };
+    let mut deps = get_deps_2(MockOpts::new(), args, all_records)?;
+    let mut mock_input = MockInputable::new();
+    mock_input.expect_select_highlighted().returning(|_, _, _| Some(0));
+    mock_input.expect_get_input().never();
+    deps.input = Rc::new(mock_input);
+
+    let output = SharedOutput::default();
+    GetHandler::new(Rc::new(RefCell::new(deps)))
+        .with_output(Box::new(output.clone()))
+        .get_command(&Some("echo".to_string()))?;
+
+    assert_eq!(String::from_utf8(output.0.take()).unwrap(), "echo $CMD_TEST_UNSET_PRINT ${HOME}\n");
+
+    Ok(())
+}
+
+#[test]
+fn discovered_tasks_are_offered_and_saved_once_used() -> Result<(), Box<dyn std::error::Error>> {
+    initialize();
+    let dir = tempfile::tempdir()?;
+    std::fs::write(dir.path().join("Makefile"), "test: ## Run the tests\n\t./run-tests\n")?;
+
+    let mock_opts = MockOpts::from(|opts: &Vec<String>| {
+        opts.iter()
+            .position(|opt| opt.starts_with("make test"))
+            .unwrap_or(0)
+    });
+    let mut deps = get_deps(Rc::clone(&mock_opts), vec!["1,git log,0"])?;
+    deps.tasks = TaskDiscovery::new(dir.path().to_path_buf());
+    let mut mock_os = MockOSServiceImpl::new();
+    mock_os
+        .expect_execute_command()
+        .withf(|command, _| command == "make test")
+        .times(3)
+        .returning_st({
+            let mut runs = 0;
+            move |_, _| {
+                runs += 1;
+                let code = if runs == 1 { 2 } else { 0 };
+                Ok(ExecutionOutcome { code: Some(code), ..ExecutionOutcome::default() })
+            }
+        });
+    deps.os = Rc::new(mock_os);
+    let deps_ref = Rc::new(RefCell::new(deps));
+
+    // Not stored until it is used successfully
+    assert!(deps_ref.as_ref().borrow_mut().controller.find_command("make test").is_none());
+    let result = GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("test".to_string()));
+    assert!(matches!(result, Err(CmdError::ExitCodeError(2))));
+    assert!(deps_ref.as_ref().borrow_mut().controller.find_command("make test").is_none());
+    let history = deps_ref.as_ref().borrow_mut().controller.get_history(HistoryFilters::default())?;
+    assert_eq!(history[0].cmd_id, None);
+
+    GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("test".to_string()))?;
+    assert_eq!(
+        mock_opts.as_ref().borrow().captures.options_for_command,
+        vec!["make test  # Makefile: Run the tests"]
+    );
+
+    let saved = deps_ref.as_ref().borrow_mut().controller.find_command("make test").unwrap();
+    assert_eq!(saved.description, Some("Makefile: Run the tests".to_string()));
+    assert_eq!(saved.used_times, 1);
+    let history = deps_ref.as_ref().borrow_mut().controller.get_history(HistoryFilters::default())?;
+    assert_eq!(history[0].cmd_id, Some(saved.id));
+
+    // Then it is offered once, as the saved command
+    GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("test".to_string()))?;
+    assert_eq!(mock_opts.as_ref().borrow().captures.options_for_command.len(), 1);
+    let saved = deps_ref.as_ref().borrow_mut().controller.find_command("make test").unwrap();
+    assert_eq!(saved.used_times, 2);
+
+    Ok(())
+}
+
+#[test]
+fn project_commands_are_offered_and_their_use_is_stored_locally() -> Result<(), Box<dyn std::error::Error>> {
+    initialize();
+    let dir = tempfile::tempdir()?;
+    let file = dir.path().join(".cmd.toml");
+    let content = "\
+[[commands]]
+command = \"kubectl logs -f {pod}\"
+description = \"Follow the logs of a pod\"
+tags = [\"k8s\"]
+
+[[commands]]
+command = \"kubectl apply -f deploy.yaml\"
+
+[providers]
+pod = \"kubectl get pods -o name\"
+";
+    std::fs::write(&file, content)?;
+
+    let args = Cli {
+        command: Some(Commands::Get { pattern: None, tags: vec!["k8s".to_string()] }),
+        ..Cli::default()
+    };
+    let mock_opts = MockOpts::from(|opts: &Vec<String>| {
+        opts.iter()
+            .position(|opt| opt == "pod/web-1")
+            .unwrap_or(0)
+    });
+    let mut deps = get_deps_2(Rc::clone(&mock_opts), args, vec!["1,kubectl get pods,0"])?;
+    deps.project = ProjectCommands::load(dir.path(), &[dir.path().to_path_buf()]);
+    let mut mock_os = MockOSServiceImpl::new();
+    mock_os
+        .expect_capture_output()
+        .withf(|command, _timeout| command == "kubectl get pods -o name")
+        .returning_st(|_, _| Ok("pod/api-1\npod/web-1\n".to_string()));
+    mock_os
+        .expect_execute_command()
+        .withf(|command, _| command == "kubectl logs -f pod/web-1")
+        .times(1)
+        .returning_st(|_, _| Ok(ExecutionOutcome { code: Some(0), ..ExecutionOutcome::default() }));
+    deps.os = Rc::new(mock_os);
+    let deps_ref = Rc::new(RefCell::new(deps));
+
+    // Only the tagged project command matches `-t k8s`
+    GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("kubectl".to_string()))?;
+
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    let stored = controller.find_command("kubectl logs -f {pod}").unwrap();
+    assert_eq!(stored.description, Some("Follow the logs of a pod".to_string()));
+    assert_eq!(stored.tags, vec!["k8s"]);
+    assert_eq!(stored.used_times, 1);
+    assert!(controller.find_command("kubectl apply -f deploy.yaml").is_none());
+    // The file is left as it is
+    assert_eq!(std::fs::read_to_string(&file)?, content);
+
+    Ok(())
+}
+
+#[test]
+fn untrusted_project_commands_do_not_run_inline_providers() -> Result<(), Box<dyn std::error::Error>> {
+    initialize();
+    let dir = tempfile::tempdir()?;
+    std::fs::write(
+        dir.path().join(".cmd.toml"),
+        "[[commands]]\ncommand = \"kubectl logs {pod:$(kubectl get pods -o name)}\"\n"
+    )?;
+
+    let mock_opts = MockOpts::from(|opts: &Vec<String>| {
+        opts.iter()
+            .position(|opt| opt.contains("kubectl logs"))
+            .unwrap_or(0)
+    });
+    let mut deps = get_deps(mock_opts, vec!["1,git log,0"])?;
+    deps.project = ProjectCommands::load(dir.path(), &[]);
+    let mut mock_os = MockOSServiceImpl::new();
+    mock_os.expect_capture_output().times(0);
+    mock_os
+        .expect_execute_command()
+        .withf(|command, _| command == "kubectl logs git")
+        .times(2)
+        .returning_st(|_, _| Ok(ExecutionOutcome { code: Some(0), ..ExecutionOutcome::default() }));
+    deps.os = Rc::new(mock_os);
+    let deps_ref = Rc::new(RefCell::new(deps));
+
+    // The value is typed instead, also once the command is stored
+    GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("kubectl".to_string()))?;
+    GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("kubectl".to_string()))?;
+
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    let stored = controller.find_command("kubectl logs {pod:$(kubectl get pods -o name)}").unwrap();
+    assert_eq!(stored.used_times, 2);
+
+    Ok(())
+}
+
+#[test]
+fn import_history_saves_new_commands_with_their_usage() -> Result<(), Box<dyn std::error::Error>> {
+    initialize();
+    let dir = tempfile::tempdir()?;
+    let file = dir.path().join(".zsh_history");
+    std::fs::write(
+        &file,
+        ": 100:0;cargo test\n: 200:0;ls -la\n: 300:0;cargo test\n: 400:0;git log\n: 500:0;cd /tmp\n"
+    )?;
+
+    let deps = get_deps(MockOpts::new(), vec!["1,git log,0"])?;
+    let deps_ref = Rc::new(RefCell::new(deps));
+    ImportHandler::new(Rc::clone(&deps_ref)).import_history(
+        Some(ShellKind::Zsh),
+        Some(file.to_string_lossy().to_string()),
+        10
+    )?;
+
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    let imported = controller.find_command("cargo test").unwrap();
+    assert_eq!(imported.used_times, 2);
+    assert_eq!(imported.last_used, Some(300));
+    assert!(controller.find_command("ls -la").is_none());
+    assert!(controller.find_command("cd /tmp").is_none());
+    assert_eq!(controller.get_commands(String::new()).len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn import_history_escapes_braces() -> Result<(), Box<dyn std::error::Error>> {
+    initialize();
+    let dir = tempfile::tempdir()?;
+    let file = dir.path().join(".zsh_history");
+    std::fs::write(
+        &file,
+        ": 100:0;git stash show stash@{0}\n: 200:0;awk '{print $1}' log\n: 300:0;git stash show stash@{0}\n"
+    )?;
+
+    let deps = get_deps(MockOpts::new(), vec!["1,git log,0"])?;
+    let deps_ref = Rc::new(RefCell::new(deps));
+    ImportHandler::new(Rc::clone(&deps_ref)).import_history(
+        Some(ShellKind::Zsh),
+        Some(file.to_string_lossy().to_string()),
+        10
+    )?;
+
+    // Saved so that they run as they were typed, without asking for placeholders
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    let stash = controller.find_command("git stash show stash@{{0}}").unwrap();
+    assert_eq!(stash.used_times, 2);
+    assert!(controller.find_command("awk '{{print $1}}' log").is_some());
+    for command in ["git stash show stash@{{0}}", "awk '{{print $1}}' log"] {
+        let template = Template::parse(command);
+        assert!(template.placeholders.is_empty());
+        assert_eq!(template.render(&[]), command.replace("{{", "{").replace("}}", "}"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn suggest_saves_the_picked_templates() -> Result<(), Box<dyn std::error::Error>> {
+    initialize();
+    let dir = tempfile::tempdir()?;
+    let file = dir.path().join(".bash_history");
+    std::fs::write(&file, "ssh -p 2222 deploy@host-1\nssh -p 2222 deploy@host-2\ngit log\n")?;
+
+    let deps = get_deps(MockOpts::new(), vec!["1,git log,0"])?;
+    let deps_ref = Rc::new(RefCell::new(deps));
+    SuggestHandler::new(Rc::clone(&deps_ref)).suggest(SuggestQuery {
+        shell: Some(ShellKind::Bash),
+        file: Some(file.to_string_lossy().to_string()),
+        recent: 100,
+        limit: 10,
+    })?;
+
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    assert!(controller.find_command("ssh -p 2222 {}").is_some());
+    assert_eq!(controller.get_commands(String::new()).len(), 2);
+
+    Ok(())
+}
+
 #[test]
 fn get_command_test() -> Result<(), CmdError> {
     initialize();
@@ -174,6 +579,9 @@ fn get_command_test_2() -> Result<(), CmdError> {
         get_command: None,
         verbose: true,
         dry_run: false,
+        here: false,
+        substring: false,
+        print: false,
         generator: None,
     };
 
@@ -325,6 +733,360 @@ fn get_command_test_many_times_2() -> Result<(), CmdError> {
     Ok(())
 }
 
+#[test]
+fn get_command_fuzzy_pattern() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec!["1,git log,0", "2,git branch,0", "3,git commit -m {},0", "4,ls -l,0"];
+
+    let mock_opts = MockOpts::new();
+
+    let deps = get_deps(Rc::clone(&mock_opts), all_records)?;
+    let mut get_handler = GetHandler::new(Rc::new(RefCell::new(deps)));
+    get_handler.get_command(&Some("gcm".to_string()))?;
+
+    let captures = mock_opts.as_ref().take().captures.options_for_command;
+    log_debug!("Captures: {:?}", captures);
+
+    assert_eq!(captures, vec!["git commit -m {}".to_string()]);
+
+    Ok(())
+}
+
+#[test]
+fn get_command_with_tag_filter() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec!["1,kubectl get pods,0", "2,kubectl logs api,0", "3,git log,0"];
+
+    let mock_opts = MockOpts::new();
+
+    let args = Cli {
+        command: Some(Commands::Get { pattern: Some("log".to_string()), tags: vec!["K8s".to_string()] }),
+        get_command: None,
+        verbose: true,
+        dry_run: false,
+        here: false,
+        substring: false,
+        print: false,
+        generator: None,
+    };
+
+    let mut deps = get_deps_2(Rc::clone(&mock_opts), args, all_records)?;
+    let record = deps.controller.find_command("kubectl logs api").unwrap();
+    deps.controller.add_tag(&record, "k8s")?;
+    deps.controller.describe_command(record, Some("Tail the API logs".to_string()))?;
+
+    let mut get_handler = GetHandler::new(Rc::new(RefCell::new(deps)));
+    get_handler.get_command(&Some("log".to_string()))?;
+
+    let captures = mock_opts.as_ref().take().captures.options_for_command;
+    log_debug!("Captures: {:?}", captures);
+
+    assert_eq!(captures, vec!["kubectl logs api  # Tail the API logs".to_string()]);
+
+    Ok(())
+}
+
+#[test]
+fn get_command_named_placeholders() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec![
+        "1,git checkout {branch} && git push origin {branch} -o {opt:ci.skip},0",
+        "2,ls -l,0",
+    ];
+
+    let mock_opts = MockOpts::new();
+
+    let deps = get_deps(mock_opts, all_records)?;
+    let deps_ref = Rc::new(RefCell::new(deps));
+    let mut get_handler = GetHandler::new(Rc::clone(&deps_ref));
+    get_handler.get_command(&Some("checkout".to_string()))?;
+
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    let history = controller.get_history(HistoryFilters::default())?;
+
+    assert_eq!(
+        history.first().unwrap().command,
+        "git checkout git && git push origin git -o ci.skip"
+    );
+    assert!(controller.find_command("git checkout git && git push origin git -o ci.skip").is_some());
+
+    Ok(())
+}
+
+#[test]
+fn get_command_remembers_placeholder_values() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec!["1,kubectl logs {pod},0", "2,ls -l,0"];
+
+    // Always the template, and the first value when picking a value
+    let mock_opts = MockOpts::from(|opts: &Vec<String>| {
+        opts.iter()
+            .position(|opt| opt == "kubectl logs {pod}")
+            .unwrap_or(0)
+    });
+
+    let deps = get_deps(Rc::clone(&mock_opts), all_records)?;
+    let deps_ref = Rc::new(RefCell::new(deps));
+    for _ in 0..2 {
+        let mut get_handler = GetHandler::new(Rc::clone(&deps_ref));
+        get_handler.get_command(&Some("logs".to_string()))?;
+    }
+
+    let captures = mock_opts.as_ref().take().captures.options_for_command;
+    log_debug!("Captures: {:?}", captures);
+    assert_eq!(captures, vec!["git".to_string(), "New value...".to_string()]);
+
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    let history = controller.get_history(HistoryFilters::default())?;
+    assert_eq!(
+        history
+            .iter()
+            .map(|h| h.command.clone())
+            .collect::<Vec<_>>(),
+        vec!["kubectl logs git", "kubectl logs git"]
+    );
+    assert_eq!(controller.get_placeholder_values("kubectl logs {pod}", "pod"), vec!["git"]);
+
+    Ok(())
+}
+
+#[test]
+fn get_command_forgets_placeholder_values_of_failed_commands() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec!["1,kubectl logs {pod},0"];
+
+    let mut deps = get_deps(MockOpts::new(), all_records)?;
+    let mut mock_os = MockOSServiceImpl::new();
+    mock_os.expect_execute_command().returning_st(|_, _| {
+        Ok(ExecutionOutcome { code: Some(1), ..ExecutionOutcome::default() })
+    });
+    deps.os = Rc::new(mock_os);
+
+    let deps_ref = Rc::new(RefCell::new(deps));
+    let result = GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("logs".to_string()));
+    assert!(matches!(result, Err(CmdError::ExitCodeError(1))));
+
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    assert_eq!(controller.get_history(HistoryFilters::default())?.len(), 1);
+    assert!(controller.get_placeholder_values("kubectl logs {pod}", "pod").is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn get_command_choice_placeholders() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec!["1,deploy --env {env:dev|staging|prod},0", "2,ls -l,0"];
+
+    let mock_opts = MockOpts::from(|opts: &Vec<String>| {
+        opts.iter()
+            .position(|opt| opt == "staging" || opt.starts_with("deploy"))
+            .unwrap_or(0)
+    });
+
+    let deps = get_deps(Rc::clone(&mock_opts), all_records)?;
+    let deps_ref = Rc::new(RefCell::new(deps));
+    let mut get_handler = GetHandler::new(Rc::clone(&deps_ref));
+    get_handler.get_command(&Some("deploy".to_string()))?;
+
+    let captures = mock_opts.as_ref().take().captures.options_for_command;
+    assert_eq!(captures, vec!["dev", "staging", "prod"]);
+
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    let history = controller.get_history(HistoryFilters::default())?;
+    assert_eq!(history.first().unwrap().command, "deploy --env staging");
+
+    Ok(())
+}
+
+#[test]
+fn get_command_cancelling_a_choice_runs_nothing() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec!["1,deploy --env {env:dev|staging|prod},0"];
+
+    let mut deps = get_deps(MockOpts::new(), all_records)?;
+    let mut mock_input = MockInputable::new();
+    mock_input.expect_select_highlighted().returning(|_, _, _| Some(0));
+    mock_input.expect_select_option().returning(|_, _| None);
+    deps.input = Rc::new(mock_input);
+    let mut mock_os = MockOSServiceImpl::new();
+    mock_os.expect_execute_command().never();
+    deps.os = Rc::new(mock_os);
+
+    let deps_ref = Rc::new(RefCell::new(deps));
+    let result = GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("deploy".to_string()));
+    assert!(matches!(result, Err(CmdError::NoSelectionError)));
+
+    Ok(())
+}
+
+#[test]
+fn get_command_provider_placeholders() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec![
+        "1,kubectl -n {ns} logs {pod:$(kubectl get pods -o name)} && kubectl -n {ns} describe {other:$(kubectl get pods -o name)},0",
+        "2,ls -l,0",
+    ];
+
+    let mock_opts = MockOpts::from(|opts: &Vec<String>| {
+        opts.iter()
+            .position(|opt| opt.starts_with("kubectl -n") || opt == "pod/web-1" || opt == "kube-system")
+            .unwrap_or(0)
+    });
+
+    let mut deps = get_deps(Rc::clone(&mock_opts), all_records)?;
+    deps.config.providers.insert("ns".to_string(), "kubectl get ns -o name".to_string());
+
+    // Each provider runs once, even when several placeholders use it
+    let mut mock_os = MockOSServiceImpl::new();
+    mock_os.expect_execute_command().returning_st(|_, _| Ok(ExecutionOutcome { code: Some(0), ..ExecutionOutcome::default() }));
+    mock_os
+        .expect_capture_output()
+        .times(2)
+        .returning_st(|command, _timeout| {
+            match command {
+                "kubectl get pods -o name" => Ok("pod/api-1\npod/web-1\n\n".to_string()),
+                "kubectl get ns -o name" => Ok("default\nkube-system\n".to_string()),
+                _ => Err(CmdError::OSProcessError(command.to_string())),
+            }
+        });
+    deps.os = Rc::new(mock_os);
+
+    let deps_ref = Rc::new(RefCell::new(deps));
+    let mut get_handler = GetHandler::new(Rc::clone(&deps_ref));
+    get_handler.get_command(&Some("logs".to_string()))?;
+
+    let captures = mock_opts.as_ref().take().captures.options_for_command;
+    assert_eq!(captures, vec!["pod/api-1", "pod/web-1", "New value..."]);
+
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    let history = controller.get_history(HistoryFilters::default())?;
+    assert_eq!(
+        history.first().unwrap().command,
+        "kubectl -n kube-system logs pod/web-1 && kubectl -n kube-system describe pod/web-1"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn get_command_expands_env_vars() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec!["1,echo ${CMD_TEST_UNSET:-fallback} $CMD_TEST_UNSET_TOO '$HOME',0"];
+
+    let mock_opts = MockOpts::new();
+
+    let mut deps = get_deps(mock_opts.clone(), all_records.clone())?;
+    let mut mock_os = MockOSServiceImpl::new();
+    mock_os
+        .expect_execute_command()
+        .withf(|command, recorded| {
+            command == "echo fallback git '$HOME'" && recorded == "echo fallback git '$HOME'"
+        })
+        .times(1)
+        .returning_st(|_, _| Ok(ExecutionOutcome { code: Some(0), ..ExecutionOutcome::default() }));
+    deps.os = Rc::new(mock_os);
+    let deps_ref = Rc::new(RefCell::new(deps));
+    GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("echo".to_string()))?;
+
+    // Or leave them for the shell
+    let mut deps = get_deps(mock_opts.clone(), all_records.clone())?;
+    deps.config.expand_env = false;
+    let raw_deps_ref = Rc::new(RefCell::new(deps));
+    GetHandler::new(Rc::clone(&raw_deps_ref)).get_command(&Some("echo".to_string()))?;
+
+    // Or run them expanded but keep their values, secrets among them, out of the history
+    let mut deps = get_deps(mock_opts, all_records)?;
+    deps.config.record_expanded = false;
+    let mut mock_os = MockOSServiceImpl::new();
+    mock_os
+        .expect_execute_command()
+        .withf(|command, recorded| {
+            command == "echo fallback git '$HOME'" && recorded == "echo ${CMD_TEST_UNSET:-fallback} $CMD_TEST_UNSET_TOO '$HOME'"
+        })
+        .times(1)
+        .returning_st(|_, _| Ok(ExecutionOutcome { code: Some(0), ..ExecutionOutcome::default() }));
+    deps.os = Rc::new(mock_os);
+    let unrecorded_deps_ref = Rc::new(RefCell::new(deps));
+    GetHandler::new(Rc::clone(&unrecorded_deps_ref)).get_command(&Some("echo".to_string()))?;
+
+    // The history has the line that ran
+    let history = deps_ref.as_ref().borrow_mut().controller.get_history(HistoryFilters::default())?;
+    assert_eq!(history.first().unwrap().command, "echo fallback git '$HOME'");
+
+    let history = raw_deps_ref.as_ref().borrow_mut().controller.get_history(HistoryFilters::default())?;
+    assert_eq!(history.first().unwrap().command, "echo ${CMD_TEST_UNSET:-fallback} $CMD_TEST_UNSET_TOO '$HOME'");
+
+    let history = unrecorded_deps_ref.as_ref().borrow_mut().controller.get_history(HistoryFilters::default())?;
+    assert_eq!(history.first().unwrap().command, "echo ${CMD_TEST_UNSET:-fallback} $CMD_TEST_UNSET_TOO '$HOME'");
+
+    Ok(())
+}
+
+#[test]
+fn get_command_records_history() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec!["1,git log,0", "2,git branch,0", "3,git commit -m {},0"];
+
+    let mock_opts = MockOpts::new();
+
+    let deps = get_deps(mock_opts, all_records)?;
+    let deps_ref = Rc::new(RefCell::new(deps));
+    let mut get_handler = GetHandler::new(Rc::clone(&deps_ref));
+    get_handler.get_command(&None)?;
+
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    let history = controller.get_history(HistoryFilters::default())?;
+
+    log_debug!("History: {:?}", history);
+    assert_eq!(history.len(), 1);
+    assert_eq!(history.first().unwrap().exit_code, Some(0));
+    assert_eq!(
+        history.first().unwrap().cwd,
+        std::env::current_dir()?.to_string_lossy().to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn get_command_returns_the_exit_code() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec!["1,git log,0"];
+
+    let mut deps = get_deps(MockOpts::new(), all_records)?;
+    let mut mock_os = MockOSServiceImpl::new();
+    mock_os.expect_execute_command().returning_st(|_, _| {
+        Ok(ExecutionOutcome { code: Some(3), signal: None, duration: Duration::from_millis(42) })
+    });
+    deps.os = Rc::new(mock_os);
+
+    let deps_ref = Rc::new(RefCell::new(deps));
+    let result = GetHandler::new(Rc::clone(&deps_ref)).get_command(&None);
+    assert!(matches!(result, Err(CmdError::ExitCodeError(3))));
+
+    // A failed command is still recorded
+    let controller = &mut deps_ref.as_ref().borrow_mut().controller;
+    let history = controller.get_history(HistoryFilters::default())?;
+    assert_eq!(history.first().unwrap().exit_code, Some(3));
+    assert_eq!(history.first().unwrap().duration_ms, 42);
+
+    Ok(())
+}
+
+#[test]
+fn get_command_without_matches() -> Result<(), CmdError> {
+    initialize();
+    let all_records = vec!["1,git log,0"];
+
+    let deps = get_deps(MockOpts::new(), all_records)?;
+    let deps_ref = Rc::new(RefCell::new(deps));
+    let result = GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("kubectl".to_string()));
+    assert!(matches!(result, Err(CmdError::NoMatchError)));
+
+    Ok(())
+}
+
 #[test]
 fn get_command_test_pattern() -> Result<(), CmdError> {
     initialize();
@@ -1,15 +1,29 @@
+use git2::{ Repository, Signature };
+use regex::Regex;
 use rusqlite::Connection;
 
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
 use crate::args::{ Cli, Commands };
 
 
 
 use crate::cmd::cmd_get::GetHandler;
 use crate::error::CmdError;
+use crate::models::execution_outcome::ExecutionOutcome;
+use crate::services::cmd_extension_git::{ argument_values, CmdExtensionGit, GitValues };
 use crate::services::cmd_service_sql::CmdServiceSQL;
 use crate::services::controller::Controller;
+use crate::services::extension_registry::ExtensionRegistry;
+use crate::services::task_discovery::TaskDiscovery;
+use crate::services::project_commands::ProjectCommands;
+use crate::services::plugin::PluginExtension;
+use crate::services::template::Placeholder;
+use crate::traits::cmd_extension::{ CmdExtension, PlaceholderContext, PlaceholderValue };
+use crate::traits::cmd_service::HistoryFilters;
+use crate::services::ranking::Ranking;
+use crate::config::Config;
 
 use crate::services::os_service::MockOSServiceImpl;
 use crate::traits::cmd_service::CmdService;
@@ -17,6 +31,7 @@ use crate::traits::inputable::{ MockInputable };
 use crate::{ Deps, log_info, log_debug };
 
 use super::mocks::mock_opts::{ MutRef, MockOpts };
+use super::plugin_test::{ write_plugin, KUBECTL_PLUGIN };
 
 pub fn initialize() {
     let _ = env_logger::builder().is_test(true).filter_level(log::LevelFilter::Debug).try_init();
@@ -30,6 +45,9 @@ fn get_deps<'a>(
         command: Some(Commands::Add { pattern: false, execute: false }),
         verbose: true,
         dry_run: false,
+        here: false,
+        substring: false,
+        print: false,
         generator: None,
     };
     get_deps_2(mock_opts, args, all)
@@ -59,10 +77,25 @@ fn get_deps_2<'a>(
     let controller = Controller::<CmdServiceSQL> {
         all: all_cmd_service.clone(),
         used: all_cmd_service,
+        ranking: Ranking::default().build(),
+        dry_run: args.dry_run,
     };
 
     let mut mock_input = MockInputable::new();
     mock_input.expect_get_input().returning(|_| "git".to_string());
+    mock_input.expect_get_optional_input().returning(|_| None);
+    mock_input.expect_get_input_with_default().returning(|_, default| default);
+
+    let highlighted_opts = Rc::clone(&mock_opts);
+    mock_input.expect_select_highlighted().returning_st(move |opts, _highlights, _maybe_prompt| {
+        let x = highlighted_opts.as_ref();
+        let opts = opts.to_vec();
+
+        let result = x.borrow_mut().get_selected_record(&opts);
+        x.borrow_mut().capture_options_for_command(opts.clone());
+
+        Some(std::cmp::min(result, opts.len() - 1))
+    });
 
     mock_input.expect_select_option().returning_st(move |opts, _maybe_prompt| {
         let prompt = match _maybe_prompt {
@@ -89,12 +122,21 @@ fn get_deps_2<'a>(
     });
 
     let mut mock_os = MockOSServiceImpl::new();
-    mock_os.expect_execute_command().returning_st(|arg| {
+    mock_os.expect_execute_command().returning_st(|arg, _| {
         log_info!("Running command {}", arg);
-        Ok(true)
+        Ok(ExecutionOutcome { code: Some(0), ..ExecutionOutcome::default() })
     });
 
-    Ok(Deps { args, controller, input: Rc::new(mock_input), os: Rc::new(mock_os) })
+    Ok(Deps {
+        args,
+        config: Config::default(),
+        controller,
+        input: Rc::new(mock_input),
+        os: Rc::new(mock_os),
+        extensions: ExtensionRegistry::default(),
+        tasks: TaskDiscovery::default(),
+        project: ProjectCommands::default(),
+    })
 }
 
 #[test]
@@ -143,4 +185,205 @@ fn test_git_ext_2() -> Result<(), CmdError> {
     log_debug!("Captures: {:?}", captures);
 
     result
-}
\ No newline at end of file
+}
+
+/// A repository with an initial commit, and a branch for each name
+fn temp_repo(branches: &[&str]) -> Result<tempfile::TempDir, Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let repo = Repository::init(dir.path())?;
+    let signature = Signature::now("cmd", "cmd@example.com")?;
+    let tree = repo.find_tree(repo.index()?.write_tree()?)?;
+    let commit = repo.commit(Some("HEAD"), &signature, &signature, "Initial commit", &tree, &[])?;
+    let commit = repo.find_commit(commit)?;
+    for branch in branches {
+        repo.branch(branch, &commit, false)?;
+    }
+    Ok(dir)
+}
+
+#[test]
+fn git_extension_offers_branches() -> Result<(), Box<dyn std::error::Error>> {
+    initialize();
+    let repo = temp_repo(&["feature-a", "feature-b"])?;
+    let all_records = vec!["1,git checkout {} && git merge --no-ff {branch},0"];
+
+    let mock_opts = MockOpts::from(|opts: &Vec<String>| {
+        opts.iter()
+            .position(|opt| opt == "feature-a")
+            .or_else(|| opts.iter().position(|opt| opt == "feature-b"))
+            .unwrap_or(0)
+    });
+    let mut deps = get_deps(Rc::clone(&mock_opts), all_records)?;
+    deps.extensions.register(Box::new(CmdExtensionGit::new(repo.path().to_path_buf())));
+
+    let deps_ref = Rc::new(RefCell::new(deps));
+    GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("checkout".to_string()))?;
+
+    let captures = mock_opts.as_ref().take().captures.options_for_command;
+    assert!(captures.contains(&"feature-b".to_string()));
+    let history = deps_ref.as_ref().borrow_mut().controller.get_history(HistoryFilters::default())?;
+    assert_eq!(history.first().unwrap().command, "git checkout feature-a && git merge --no-ff feature-a");
+
+    Ok(())
+}
+
+fn values(git: &CmdExtensionGit, before: &str) -> Option<Vec<String>> {
+    let placeholder = Placeholder::default();
+    let context = PlaceholderContext { template: "", placeholder: &placeholder, before: before.to_string() };
+    git.placeholder_values(&context).map(|values| values.iter().map(|value| value.display()).collect())
+}
+
+#[test]
+fn git_arguments_are_recognized() {
+    use GitValues::*;
+
+    assert_eq!(argument_values("git checkout "), vec![LocalBranches, RemoteBranches]);
+    assert_eq!(argument_values("cd app && git -C ../lib rebase -i "), vec![LocalBranches, RemoteBranches]);
+    assert_eq!(argument_values("git push "), vec![Remotes]);
+    assert_eq!(argument_values("git push --force-with-lease origin "), vec![LocalBranches, Tags]);
+    assert_eq!(argument_values("git cherry-pick -x "), vec![Commits]);
+    assert_eq!(argument_values("git tag -d "), vec![Tags]);
+    assert_eq!(argument_values("git tag v2.0 "), vec![Commits]);
+    assert_eq!(argument_values("git worktree remove "), vec![Worktrees]);
+    assert_eq!(argument_values("git worktree add ../hotfix "), vec![LocalBranches, RemoteBranches]);
+    assert_eq!(argument_values("git branch -D "), vec![LocalBranches]);
+
+    // New names, messages and other commands are typed
+    assert!(argument_values("git checkout -b ").is_empty());
+    assert!(argument_values("git commit -m ").is_empty());
+    assert!(argument_values("git tag ").is_empty());
+    assert!(argument_values("git checkout main && ls ").is_empty());
+}
+
+#[test]
+fn git_extension_offers_repository_values() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = temp_repo(&["feature-a"])?;
+    let repo = Repository::open(dir.path())?;
+    let signature = Signature::now("cmd", "cmd@example.com")?;
+    let head = repo.head()?.peel_to_commit()?;
+    let fix = repo.commit(Some("HEAD"), &signature, &signature, "Fix the build", &head.tree()?, &[&head])?;
+    let fix = repo.find_commit(fix)?;
+    repo.tag_lightweight("v1.0", fix.as_object(), false)?;
+    repo.remote("origin", "https://example.com/repo.git")?;
+    repo.reference("refs/remotes/origin/main", fix.id(), false, "test")?;
+    repo.reference_symbolic("refs/remotes/origin/HEAD", "refs/remotes/origin/main", false, "test")?;
+    let worktree_dir = tempfile::tempdir()?;
+    let worktree_path = worktree_dir.path().join("hotfix");
+    repo.worktree("hotfix", &worktree_path, None)?;
+
+    let git = CmdExtensionGit::new(dir.path().to_path_buf());
+    let short = fix.as_object().short_id()?.as_str().unwrap().to_string();
+
+    let branches = values(&git, "git merge ").unwrap();
+    assert!(branches.contains(&"feature-a".to_string()));
+    assert!(branches.contains(&"origin/main".to_string()));
+    assert!(!branches.contains(&"origin/HEAD".to_string()));
+    assert_eq!(values(&git, "git push "), Some(vec!["origin".to_string()]));
+    assert!(values(&git, "git push origin ").unwrap().contains(&"v1.0".to_string()));
+    assert_eq!(values(&git, "git tag -d ").unwrap(), vec!["v1.0"]);
+    let commits = values(&git, "git cherry-pick ").unwrap();
+    assert_eq!(commits.first().unwrap(), &format!("{}  # Fix the build", short));
+    assert_eq!(commits.len(), 2);
+    assert_eq!(
+        values(&git, "git worktree remove ").unwrap(),
+        vec![format!("{}  # hotfix", worktree_path.canonicalize()?.to_string_lossy())]
+    );
+    assert!(values(&git, "git commit -m ").is_none());
+
+    // Outside of a repository the value is typed instead
+    let outside = tempfile::tempdir()?;
+    assert!(values(&CmdExtensionGit::new(outside.path().to_path_buf()), "git checkout ").is_none());
+
+    Ok(())
+}
+
+/// Takes over `deploy` commands, and fails them
+struct DeployExtension {
+    pattern: Regex,
+}
+
+impl CmdExtension for DeployExtension {
+    fn name(&self) -> &str {
+        "deploy"
+    }
+
+    fn pattern(&self) -> &Regex {
+        &self.pattern
+    }
+
+    fn placeholder_values(&self, _context: &PlaceholderContext) -> Option<Vec<PlaceholderValue>> {
+        Some(vec![PlaceholderValue::new("staging"), PlaceholderValue::described("prod", "Production")])
+    }
+
+    fn execute(&self, _template: &str, command: &str) -> Option<Result<ExecutionOutcome, CmdError>> {
+        log_info!("Deploying with '{}'", command);
+        Some(Ok(ExecutionOutcome { code: Some(7), ..ExecutionOutcome::default() }))
+    }
+}
+
+#[test]
+fn extensions_are_matched_by_pattern_and_can_run_commands() -> Result<(), Box<dyn std::error::Error>> {
+    initialize();
+    let all_records = vec!["1,deploy --env {},0", "2,echo {},0"];
+
+    let mock_opts = MockOpts::from(|opts: &Vec<String>| {
+        opts.iter()
+            .position(|opt| opt == "prod  # Production" || opt.starts_with("deploy") || opt.starts_with("echo"))
+            .unwrap_or(0)
+    });
+    let mut deps = get_deps(Rc::clone(&mock_opts), all_records)?;
+    deps.extensions.register(Box::new(DeployExtension { pattern: Regex::new(r"^deploy\b")? }));
+    let mut mock_os = MockOSServiceImpl::new();
+    mock_os.expect_execute_command().times(1).returning_st(|_, _| {
+        Ok(ExecutionOutcome { code: Some(0), ..ExecutionOutcome::default() })
+    });
+    deps.os = Rc::new(mock_os);
+
+    let deps_ref = Rc::new(RefCell::new(deps));
+    let result = GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("deploy".to_string()));
+    assert!(matches!(result, Err(CmdError::ExitCodeError(7))));
+
+    // Other commands are left to cmd, and get no values from the extension
+    GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("echo".to_string()))?;
+
+    let history = deps_ref.as_ref().borrow_mut().controller.get_history(HistoryFilters::default())?;
+    let commands = history
+        .iter()
+        .map(|entry| entry.command.as_str())
+        .collect::<Vec<_>>();
+    assert!(commands.contains(&"deploy --env prod"));
+    assert!(commands.contains(&"echo git"));
+
+    Ok(())
+}
+
+#[test]
+fn plugins_fill_placeholders_and_rewrite_the_command() -> Result<(), Box<dyn std::error::Error>> {
+    initialize();
+    let all_records = vec!["1,kubectl logs {pod},0"];
+    let dir = tempfile::tempdir()?;
+    let plugin = write_plugin(dir.path(), "cmd-plugin-kubectl", KUBECTL_PLUGIN);
+
+    let mock_opts = MockOpts::from(|opts: &Vec<String>| {
+        opts.iter()
+            .position(|opt| opt == "web-2")
+            .unwrap_or(0)
+    });
+    let mut deps = get_deps(Rc::clone(&mock_opts), all_records)?;
+    deps.extensions.register(Box::new(PluginExtension::new(plugin, Duration::from_secs(5))));
+    let mut mock_os = MockOSServiceImpl::new();
+    mock_os
+        .expect_execute_command()
+        .withf(|command, _| command == "kubectl --context dev logs web-2")
+        .times(1)
+        .returning_st(|_, _| Ok(ExecutionOutcome { code: Some(0), ..ExecutionOutcome::default() }));
+    deps.os = Rc::new(mock_os);
+
+    let deps_ref = Rc::new(RefCell::new(deps));
+    GetHandler::new(Rc::clone(&deps_ref)).get_command(&Some("kubectl".to_string()))?;
+
+    let history = deps_ref.as_ref().borrow_mut().controller.get_history(HistoryFilters::default())?;
+    assert_eq!(history[0].command, "kubectl --context dev logs web-2");
+
+    Ok(())
+}
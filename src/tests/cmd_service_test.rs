@@ -1,6 +1,6 @@
 use rusqlite::Connection;
 use vfs::{ VfsPath, MemoryFS };
-use crate::traits::cmd_service::SearchFiltersBuilder;
+use crate::traits::cmd_service::{ MatchMode, SearchFiltersBuilder, SearchOrder };
 
 use crate::{
     services::{
@@ -43,6 +43,7 @@ fn it_works() -> Result<(), error::CmdError> {
             id: 3,
             command: test_command.clone(),
             used_times: 2,
+            ..CmdRecord::default()
         };
         cmd_service.update_command(ran_cmd.clone())?;
 
@@ -70,6 +71,7 @@ fn it_works() -> Result<(), error::CmdError> {
             id: 3,
             command: test_command.clone(),
             used_times: 3,
+            ..CmdRecord::default()
         })?;
 
         let updated_commands = cmd_service.get_commands(SearchFilters::default());
@@ -169,6 +171,7 @@ fn cmd_service_sql_insert() -> Result<(), error::CmdError> {
             id: 1,
             command: String::from("git log"),
             used_times: x,
+            ..CmdRecord::default()
         };
 
         cmd_service.insert_command(cmd)?;
@@ -262,4 +265,120 @@ fn migrate_cvs_test_prod() -> Result<(), error::CmdError> {
     log_debug!("Commands: {:?}", commands);
 
     Ok(())
-}
\ No newline at end of file
+}
+#[test]
+fn cmd_service_sql_last_used() -> Result<(), error::CmdError> {
+    initialize();
+    let mut cmd_service = CmdServiceSQL::build_cmd_service(Some(Connection::open_in_memory()?))?;
+
+    cmd_service.add_command("git log".to_string())?;
+
+    let mut cmd = cmd_service.get_commands(SearchFilters::default()).first().unwrap().clone();
+    assert_eq!(cmd.last_used, None);
+
+    cmd.increase_usage();
+    cmd_service.update_command(cmd.clone())?;
+
+    let updated = cmd_service.get_commands(SearchFilters::default()).first().unwrap().clone();
+    assert_eq!(updated.last_used, cmd.last_used);
+    assert!(updated.last_used.is_some());
+
+    Ok(())
+}
+
+#[test]
+fn cmd_service_sql_escapes_patterns() -> Result<(), error::CmdError> {
+    initialize();
+    let mut cmd_service = CmdServiceSQL::build_cmd_service(Some(Connection::open_in_memory()?))?;
+
+    cmd_service.add_command("echo it's done".to_string())?;
+    cmd_service.add_command("df -h | grep 100%".to_string())?;
+    cmd_service.add_command("ls my_dir".to_string())?;
+    cmd_service.add_command("ls myXdir".to_string())?;
+
+    let search = |cmd_service: &mut CmdServiceSQL, pattern: &str| {
+        cmd_service
+            .get_commands(SearchFiltersBuilder::default().command(pattern.to_string()).build().unwrap())
+            .iter()
+            .map(|cmd| cmd.command.clone())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(search(&mut cmd_service, "it's"), vec!["echo it's done"]);
+    assert_eq!(search(&mut cmd_service, "0%"), vec!["df -h | grep 100%"]);
+    assert_eq!(search(&mut cmd_service, "my_"), vec!["ls my_dir"]);
+
+    Ok(())
+}
+
+#[test]
+fn cmd_service_sql_match_modes_and_paging() -> Result<(), error::CmdError> {
+    initialize();
+    let mut cmd_service = CmdServiceSQL::build_cmd_service(Some(Connection::open_in_memory()?))?;
+
+    for (command, used_times) in [("git log", 1), ("git log --oneline", 5), ("tig log", 3)] {
+        cmd_service.insert_command(CmdRecord {
+            command: command.to_string(),
+            used_times,
+            ..CmdRecord::default()
+        })?;
+    }
+
+    let search = |cmd_service: &mut CmdServiceSQL, filter: SearchFilters| {
+        cmd_service
+            .get_commands(filter)
+            .iter()
+            .map(|cmd| cmd.command.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let exact = SearchFiltersBuilder::default()
+        .command("git log".to_string())
+        .match_mode(MatchMode::Exact)
+        .build()
+        .unwrap();
+    assert_eq!(search(&mut cmd_service, exact), vec!["git log"]);
+
+    let prefix = SearchFiltersBuilder::default()
+        .command("git".to_string())
+        .match_mode(MatchMode::Prefix)
+        .order(SearchOrder::Command)
+        .build()
+        .unwrap();
+    assert_eq!(search(&mut cmd_service, prefix), vec!["git log", "git log --oneline"]);
+
+    let paged = SearchFiltersBuilder::default()
+        .command("log".to_string())
+        .order(SearchOrder::UsedTimes)
+        .limit(2)
+        .offset(1)
+        .build()
+        .unwrap();
+    assert_eq!(search(&mut cmd_service, paged), vec!["tig log", "git log"]);
+
+    let offset_only = SearchFiltersBuilder::default()
+        .order(SearchOrder::UsedTimes)
+        .offset(2)
+        .build()
+        .unwrap();
+    assert_eq!(search(&mut cmd_service, offset_only), vec!["git log"]);
+
+    Ok(())
+}
+
+#[test]
+fn cmd_service_sql_placeholder_values() -> Result<(), error::CmdError> {
+    initialize();
+    let mut cmd_service = CmdServiceSQL::build_cmd_service(Some(Connection::open_in_memory()?))?;
+
+    let template = "kubectl logs {pod}";
+    cmd_service.add_placeholder_value(template, "pod", "api-1")?;
+    cmd_service.add_placeholder_value(template, "pod", "web-1")?;
+    cmd_service.add_placeholder_value(template, "pod", "web-1")?;
+    cmd_service.add_placeholder_value("kubectl describe {pod}", "pod", "db-1")?;
+
+    assert_eq!(cmd_service.get_placeholder_values(template, "pod")?, vec!["web-1", "api-1"]);
+    assert!(cmd_service.get_placeholder_values(template, "container")?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use crate::{ error::CmdError, services::env_expander::expand };
+
+fn vars() -> HashMap<&'static str, &'static str> {
+    HashMap::from([
+        ("HOME", "/home/me"),
+        ("HOME_DIR", "/srv"),
+        ("EMPTY", ""),
+        ("MESSAGE", "it's done; rm -rf $HOME `id` \"*\""),
+    ])
+}
+
+fn expand_with(text: &str, asked: &mut Vec<String>) -> Result<String, CmdError> {
+    let vars = vars();
+    expand(
+        text,
+        |name| vars.get(name).map(|value| value.to_string()),
+        |name| {
+            asked.push(name.to_string());
+            format!("<{}>", name.to_lowercase())
+        }
+    )
+}
+
+fn expand_set(text: &str) -> String {
+    let mut asked = Vec::new();
+    let result = expand_with(text, &mut asked).unwrap();
+    assert!(asked.is_empty(), "asked for {:?}", asked);
+    result
+}
+
+#[test]
+fn expands_plain_and_braced_variables() {
+    assert_eq!(expand_set("cd $HOME"), "cd /home/me");
+    assert_eq!(expand_set("cd $HOME_DIR/app"), "cd /srv/app");
+    assert_eq!(expand_set("cd ${HOME}_DIR"), "cd /home/me_DIR");
+    assert_eq!(expand_set("echo \"$HOME\""), "echo \"/home/me\"");
+}
+
+#[test]
+fn expands_defaults_and_required_variables() {
+    assert_eq!(expand_set("echo ${PORT:-8080}"), "echo 8080");
+    assert_eq!(expand_set("echo ${EMPTY:-x} ${EMPTY-x}"), "echo x ");
+    assert_eq!(expand_set("echo ${PORT:-${HOME}}"), "echo /home/me");
+    assert_eq!(expand_set("echo ${HOME:?no home}"), "echo /home/me");
+
+    let err = expand_with("deploy ${TOKEN:?set TOKEN first}", &mut Vec::new()).unwrap_err();
+    assert_eq!(err.to_string(), "Environment variable error: TOKEN: set TOKEN first");
+    assert!(expand_with("echo ${EMPTY:?}", &mut Vec::new()).is_err());
+}
+
+#[test]
+fn leaves_shell_syntax_alone() {
+    for text in [
+        "awk '{print $1}' '$HOME'",
+        "echo \\$HOME $? $1 $$",
+        "echo $(date) ${#HOME} ${HOME%/me}",
+        "echo ${HOME",
+    ] {
+        assert_eq!(expand_set(text), text);
+    }
+}
+
+#[test]
+fn asks_once_for_unset_variables() {
+    let mut asked = Vec::new();
+    let result = expand_with("ssh $USER_NAME@host && echo ${USER_NAME}", &mut asked).unwrap();
+
+    assert_eq!(result, "ssh '<user_name>'@host && echo '<user_name>'");
+    assert_eq!(asked, vec!["USER_NAME"]);
+}
+
+#[test]
+fn leaves_variables_set_by_the_command_to_the_shell() {
+    for text in [
+        "for f in *.log; do gzip $f; done",
+        "HOME=/tmp; echo $HOME ${HOME:-x}",
+        "export TAG=v1 && git tag ${TAG}",
+        "(N=3; echo $N)",
+    ] {
+        assert_eq!(expand_set(text), text);
+    }
+
+    // Only after it is set
+    let mut asked = Vec::new();
+    let result = expand_with("echo $OUT; OUT=1; echo $OUT", &mut asked).unwrap();
+    assert_eq!(result, "echo '<out>'; OUT=1; echo $OUT");
+    assert_eq!(asked, vec!["OUT"]);
+    assert_eq!(expand_set("echo A=$HOME"), "echo A=/home/me");
+}
+
+#[test]
+fn quotes_values_so_the_shell_reads_them_back() {
+    let message = "it's done; rm -rf $HOME `id` \"*\"";
+
+    assert_eq!(expand_set("echo $MESSAGE"), "echo 'it'\\''s done; rm -rf $HOME `id` \"*\"'");
+    assert_eq!(expand_set("echo \"${MESSAGE}!\""), "echo \"it's done; rm -rf \\$HOME \\`id\\` \\\"*\\\"!\"");
+    assert_eq!(expand_set("echo ${EMPTY:-$MESSAGE} x$EMPTY"), "echo 'it'\\''s done; rm -rf $HOME `id` \"*\"' x");
+
+    // What the shell makes of it is the value itself
+    for text in ["printf %s $MESSAGE", "printf %s \"$MESSAGE\""] {
+        let output = std::process::Command::new("sh").arg("-c").arg(expand_set(text)).output().unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), message, "{}", text);
+    }
+}
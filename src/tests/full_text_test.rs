@@ -0,0 +1,73 @@
+use rusqlite::Connection;
+
+use crate::{
+    error::CmdError,
+    models::cmd_record::CmdRecord,
+    services::cmd_service_sql::CmdServiceSQL,
+    traits::cmd_service::{ CmdService, SearchFilters },
+    log_debug,
+};
+
+pub fn initialize() {
+    let _ = env_logger::builder().is_test(true).filter_level(log::LevelFilter::Debug).try_init();
+}
+
+fn search(cmd_service: &mut CmdServiceSQL, text: &str) -> Result<Vec<String>, CmdError> {
+    let results = cmd_service.search_full_text(text)?;
+    log_debug!("Results for {}: {:?}", text, results);
+    Ok(
+        results
+            .into_iter()
+            .map(|(cmd, _)| cmd.command)
+            .collect()
+    )
+}
+
+#[test]
+fn full_text_search_matches_words_in_any_order() -> Result<(), CmdError> {
+    initialize();
+    let mut cmd_service = CmdServiceSQL::build_cmd_service(Some(Connection::open_in_memory()?))?;
+
+    cmd_service.add_command("git checkout main".to_string())?;
+    cmd_service.add_command("kubectl rollout restart deployment".to_string())?;
+    cmd_service.add_command("git log --oneline".to_string())?;
+
+    assert_eq!(search(&mut cmd_service, "main check")?, vec!["git checkout main"]);
+    assert_eq!(search(&mut cmd_service, "deploy kube")?, vec!["kubectl rollout restart deployment"]);
+    assert!(search(&mut cmd_service, "git restart")?.is_empty());
+
+    // Operators and quotes are taken literally
+    assert!(search(&mut cmd_service, "\"git AND NOT")?.is_empty());
+    assert!(search(&mut cmd_service, "--")?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn full_text_index_follows_the_cmd_table() -> Result<(), CmdError> {
+    initialize();
+    let mut cmd_service = CmdServiceSQL::build_cmd_service(Some(Connection::open_in_memory()?))?;
+
+    cmd_service.add_command("docker ps".to_string())?;
+
+    let mut record = cmd_service.get_commands(SearchFilters::default()).first().unwrap().clone();
+    record.update_command("docker compose up");
+    cmd_service.update_command(record.clone())?;
+
+    assert!(search(&mut cmd_service, "docker ps")?.is_empty());
+    assert_eq!(search(&mut cmd_service, "docker compose")?, vec!["docker compose up"]);
+
+    // Replacing a row keeps a single entry in the index
+    cmd_service.insert_command(CmdRecord {
+        command: "docker compose up".to_string(),
+        used_times: 3,
+        ..CmdRecord::default()
+    })?;
+    assert_eq!(search(&mut cmd_service, "docker compose")?, vec!["docker compose up"]);
+
+    let replaced = cmd_service.get_commands(SearchFilters::default()).remove(0);
+    cmd_service.delete_command(replaced)?;
+    assert!(search(&mut cmd_service, "docker compose")?.is_empty());
+
+    Ok(())
+}
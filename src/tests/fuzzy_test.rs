@@ -0,0 +1,31 @@
+use crate::services::fuzzy::{ fuzzy_match, substring_positions };
+
+#[test]
+fn fuzzy_matches_subsequences() {
+    let result = fuzzy_match("gco main", "git checkout main").unwrap();
+
+    assert_eq!(result.positions, vec![0, 4, 9, 13, 14, 15, 16]);
+    assert!(fuzzy_match("gco main", "git commit -m {}").is_none());
+    assert!(fuzzy_match("xyz", "git log").is_none());
+}
+
+#[test]
+fn fuzzy_prefers_tighter_matches() {
+    let tight = fuzzy_match("log", "git log").unwrap();
+    let loose = fuzzy_match("log", "git ls-files --others -g").unwrap();
+
+    assert!(tight.score > loose.score);
+}
+
+#[test]
+fn fuzzy_uses_smart_case() {
+    assert!(fuzzy_match("readme", "cat README.md").is_some());
+    assert!(fuzzy_match("README", "cat readme.md").is_none());
+}
+
+#[test]
+fn substring_highlight_positions() {
+    assert_eq!(substring_positions("LOG", "git log"), vec![4, 5, 6]);
+    assert!(substring_positions("", "git log").is_empty());
+    assert!(substring_positions("branch", "git log").is_empty());
+}
@@ -0,0 +1,113 @@
+use rusqlite::Connection;
+
+use crate::{
+    cmd::cmd_history::parse_date,
+    error::CmdError,
+    models::history_record::HistoryRecord,
+    services::cmd_service_sql::CmdServiceSQL,
+    traits::cmd_service::{ CmdService, HistoryFilters, HistoryFiltersBuilder },
+    log_debug,
+};
+
+pub fn initialize() {
+    let _ = env_logger::builder().is_test(true).filter_level(log::LevelFilter::Debug).try_init();
+}
+
+fn entry(command: &str, cwd: &str, exit_code: Option<i32>, executed_at: i64) -> HistoryRecord {
+    HistoryRecord {
+        cmd_id: Some(1),
+        command: command.to_string(),
+        cwd: cwd.to_string(),
+        exit_code,
+        duration_ms: 10,
+        executed_at,
+        ..HistoryRecord::default()
+    }
+}
+
+/// Noon of the day in the local timezone, which is the one the date filters use
+fn local_noon(day: &str) -> Result<i64, CmdError> {
+    let connection = Connection::open_in_memory()?;
+    let timestamp = connection.query_row(
+        "SELECT CAST(strftime('%s', ?1 || ' 12:00:00', 'utc') AS INTEGER)",
+        (day,),
+        |row| row.get(0)
+    )?;
+    Ok(timestamp)
+}
+
+fn build_service() -> Result<CmdServiceSQL, CmdError> {
+    let mut cmd_service = CmdServiceSQL::build_cmd_service(Some(Connection::open_in_memory()?))?;
+    cmd_service.add_command("git checkout {}".to_string())?;
+
+    let (first_day, second_day) = (local_noon("2022-10-04")?, local_noon("2022-10-11")?);
+    cmd_service.add_history(entry("git checkout main", "/work/repo", Some(0), first_day))?;
+    cmd_service.add_history(entry("git checkout dev", "/work/repo/src", Some(1), second_day))?;
+    cmd_service.add_history(entry("git checkout main", "/work/repo2", None, second_day))?;
+    Ok(cmd_service)
+}
+
+#[test]
+fn history_is_listed_newest_first() -> Result<(), CmdError> {
+    initialize();
+    let mut cmd_service = build_service()?;
+
+    let history = cmd_service.get_history(HistoryFilters::default())?;
+    log_debug!("History: {:?}", history);
+
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.last().unwrap().command, "git checkout main");
+    assert_eq!(history.last().unwrap().cwd, "/work/repo");
+
+    Ok(())
+}
+
+#[test]
+fn history_filters() -> Result<(), CmdError> {
+    initialize();
+    let mut cmd_service = build_service()?;
+
+    let in_repo = cmd_service.get_history(
+        HistoryFiltersBuilder::default().cwd("/work/repo").build().unwrap()
+    )?;
+    assert_eq!(
+        in_repo
+            .iter()
+            .map(|h| h.cwd.as_str())
+            .collect::<Vec<_>>(),
+        vec!["/work/repo/src", "/work/repo"]
+    );
+
+    let failed = cmd_service.get_history(
+        HistoryFiltersBuilder::default().failed(true).build().unwrap()
+    )?;
+    assert_eq!(failed.len(), 2);
+
+    let on_day = cmd_service.get_history(
+        HistoryFiltersBuilder::default()
+            .command("main")
+            .since("2022-10-04")
+            .until("2022-10-04")
+            .build()
+            .unwrap()
+    )?;
+    assert_eq!(on_day.len(), 1);
+    assert_eq!(on_day.first().unwrap().cwd, "/work/repo");
+
+    let limited = cmd_service.get_history(
+        HistoryFiltersBuilder::default().limit(1_usize).build().unwrap()
+    )?;
+    assert_eq!(limited.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn history_dates_must_be_days() {
+    assert_eq!(parse_date("2022-10-04").unwrap(), "2022-10-04");
+    assert_eq!(parse_date("2024-02-29").unwrap(), "2024-02-29");
+
+    for date in ["2022-10-4", "04-10-2022", "2022/10/04", "yesterday", "2022-13-01", "2023-02-29", "2022-10-04x", ""] {
+        assert!(matches!(parse_date(date), Err(CmdError::InvalidDate(_))), "{}", date);
+    }
+}
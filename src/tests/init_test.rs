@@ -0,0 +1,28 @@
+use clap::Parser;
+
+use crate::{ args::{ Cli, Commands, ShellKind }, cmd::cmd_init::InitHandler };
+
+#[test]
+fn init_scripts_call_print_mode() {
+    let handler = InitHandler::new();
+
+    for shell in [ShellKind::Zsh, ShellKind::Bash, ShellKind::Fish] {
+        let script = handler.script(shell);
+        assert!(script.contains("command cmd get --print --"), "{:?}", shell);
+        assert!(script.contains("/dev/tty"), "{:?}", shell);
+    }
+    assert!(handler.script(ShellKind::Zsh).contains("zle -N cmd-widget"));
+    assert!(handler.script(ShellKind::Bash).contains("READLINE_LINE"));
+    assert!(handler.script(ShellKind::Fish).contains("commandline --replace"));
+}
+
+#[test]
+fn init_and_print_are_parsed() {
+    let cli = Cli::try_parse_from(["cmd", "init", "fish"]).unwrap();
+    assert!(matches!(cli.command, Some(Commands::Init { shell: ShellKind::Fish })));
+    assert!(Cli::try_parse_from(["cmd", "init", "powershell"]).is_err());
+
+    let cli = Cli::try_parse_from(["cmd", "get", "--print", "--", "git"]).unwrap();
+    assert!(cli.print);
+    assert!(matches!(cli.command, Some(Commands::Get { pattern: Some(p), .. }) if p == "git"));
+}
@@ -0,0 +1,103 @@
+use git2::Repository;
+use rusqlite::Connection;
+
+use crate::{
+    error::CmdError,
+    models::history_record::{ HistoryRecord, timestamp_now },
+    services::{
+        cmd_service_sql::CmdServiceSQL,
+        controller::Controller,
+        location::Location,
+        ranking::Ranking,
+    },
+    traits::cmd_service::{ CmdService, SearchFilters, SearchFiltersBuilder },
+    log_debug,
+};
+
+pub fn initialize() {
+    let _ = env_logger::builder().is_test(true).filter_level(log::LevelFilter::Debug).try_init();
+}
+
+fn location(repo_root: &str) -> Location {
+    Location { cwd: format!("{}/src", repo_root), repo_root: Some(repo_root.to_string()) }
+}
+
+fn run_in(service: &mut CmdServiceSQL, command: &str, location: &Location) -> Result<(), CmdError> {
+    let mut record = service
+        .get_commands(SearchFilters::default())
+        .into_iter()
+        .find(|cmd| cmd.command == command)
+        .unwrap();
+    record.increase_usage();
+    service.update_command(record.clone())?;
+    service.add_history(HistoryRecord {
+        cmd_id: Some(record.id),
+        command: command.to_string(),
+        cwd: location.cwd.clone(),
+        repo_root: location.repo_root.clone(),
+        exit_code: Some(0),
+        executed_at: timestamp_now(),
+        ..HistoryRecord::default()
+    })
+}
+
+#[test]
+fn location_detects_repository_root() -> Result<(), CmdError> {
+    initialize();
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().canonicalize()?;
+    Repository::init(&root)?;
+    std::fs::create_dir(root.join("src"))?;
+
+    let inside = Location::from_dir(&root.join("src"));
+    assert_eq!(inside.repo_root, Some(root.to_string_lossy().to_string()));
+    assert_eq!(inside.project_root(), root.to_string_lossy());
+
+    let outside = Location { cwd: "/tmp/elsewhere".to_string(), repo_root: None };
+    assert_eq!(outside.project_root(), "/tmp/elsewhere");
+
+    Ok(())
+}
+
+#[test]
+fn commands_are_filtered_and_boosted_by_project() -> Result<(), CmdError> {
+    initialize();
+    let mut service = CmdServiceSQL::build_cmd_service(Some(Connection::open_in_memory()?))?;
+    service.add_command("cargo test".to_string())?;
+    service.add_command("npm test".to_string())?;
+
+    let rust_repo = location("/work/rust");
+    let node_repo = location("/work/node");
+
+    run_in(&mut service, "cargo test", &rust_repo)?;
+    run_in(&mut service, "npm test", &node_repo)?;
+    run_in(&mut service, "npm test", &node_repo)?;
+
+    let here = service.get_commands(
+        SearchFiltersBuilder::default().location(rust_repo.clone()).build().unwrap()
+    );
+    log_debug!("Commands here: {:?}", here);
+    assert_eq!(
+        here
+            .iter()
+            .map(|cmd| cmd.command.as_str())
+            .collect::<Vec<_>>(),
+        vec!["cargo test"]
+    );
+
+    let mut controller = Controller {
+        all: service.clone(),
+        used: service,
+        ranking: Ranking::UsedTimes.build(),
+        dry_run: false,
+    };
+
+    let mut commands = controller.get_commands("test".to_string());
+    controller.rank(&mut commands, None);
+    assert_eq!(commands.first().unwrap().command, "npm test");
+
+    controller.rank(&mut commands, Some(&rust_repo));
+    assert_eq!(commands.first().unwrap().command, "cargo test");
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use rusqlite::Connection;
+
+use crate::{
+    error::CmdError,
+    services::{ cmd_service_sql::CmdServiceSQL, migrations, template::Template },
+    traits::cmd_service::{ CmdService, SearchFilters },
+    log_debug,
+};
+
+pub fn initialize() {
+    let _ = env_logger::builder().is_test(true).filter_level(log::LevelFilter::Debug).try_init();
+}
+
+#[test]
+fn migrate_new_database() -> Result<(), CmdError> {
+    initialize();
+    let connection = Connection::open_in_memory()?;
+
+    let version = migrations::migrate(&connection, None)?;
+
+    assert_eq!(version, migrations::latest_version());
+    assert_eq!(migrations::current_version(&connection)?, migrations::latest_version());
+
+    // Running it again is a no-op
+    assert_eq!(migrations::migrate(&connection, None)?, migrations::latest_version());
+
+    Ok(())
+}
+
+#[test]
+fn migrate_legacy_database_keeps_commands() -> Result<(), CmdError> {
+    initialize();
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("cmdb");
+
+    {
+        let legacy = Connection::open(&db_path)?;
+        legacy.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS cmd (id INTEGER PRIMARY KEY, command TEXT UNIQUE, used_times INTEGER);
+            INSERT INTO cmd (command, used_times) VALUES ('git log', 3);
+            "
+        )?;
+    }
+
+    let mut cmd_service = CmdServiceSQL::build_cmd_service(Some(Connection::open(&db_path)?))?;
+    let commands = cmd_service.get_commands(SearchFilters::default());
+
+    log_debug!("Commands: {:?}", commands);
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands.first().unwrap().used_times, 3);
+    assert!(migrations::backup_path(&db_path, 0).is_file());
+
+    Ok(())
+}
+
+#[test]
+fn migrate_escapes_braces_of_saved_commands() -> Result<(), CmdError> {
+    initialize();
+    let connection = Connection::open_in_memory()?;
+    connection.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS cmd (id INTEGER PRIMARY KEY, command TEXT UNIQUE, used_times INTEGER);
+        INSERT INTO cmd (command, used_times) VALUES ('awk ''{print}'' {}', 1);
+        INSERT INTO cmd (command, used_times) VALUES ('echo ${HOME} {}', 2);
+        "
+    )?;
+
+    let mut cmd_service = CmdServiceSQL::build_cmd_service(Some(connection))?;
+    let commands = cmd_service
+        .get_commands(SearchFilters::default())
+        .into_iter()
+        .map(|cmd| cmd.command)
+        .collect::<Vec<_>>();
+
+    assert_eq!(commands, vec!["awk '{{print}}' {}", "echo ${HOME} {}"]);
+    let template = Template::parse(&commands[0]);
+    assert_eq!(template.placeholders.len(), 1);
+    assert_eq!(template.render(&["file".to_string()]), "awk '{print}' file");
+
+    Ok(())
+}
+
+#[test]
+fn refuse_newer_database() -> Result<(), CmdError> {
+    initialize();
+    let connection = Connection::open_in_memory()?;
+    connection.pragma_update(None, "user_version", migrations::latest_version() + 1)?;
+
+    let result = CmdServiceSQL::build_cmd_service(Some(connection));
+
+    assert!(matches!(result, Err(CmdError::MigrationError(_))));
+
+    Ok(())
+}
@@ -2,4 +2,21 @@ pub mod mocks;
 pub mod cmd_service_test;
 pub mod all_tests;
 pub mod utils;
-pub mod cmd_git_ext_test;
\ No newline at end of file
+pub mod cmd_git_ext_test;
+pub mod migrations_test;
+pub mod history_test;
+pub mod ranking_test;
+pub mod location_test;
+pub mod fuzzy_test;
+pub mod full_text_test;
+pub mod tags_test;
+pub mod template_test;
+pub mod os_service_test;
+pub mod env_expander_test;
+pub mod program_test;
+pub mod shell_history_test;
+pub mod init_test;
+pub mod suggest_test;
+pub mod plugin_test;
+pub mod task_discovery_test;
+pub mod project_commands_test;
@@ -0,0 +1,77 @@
+use std::{ process::Command, time::{ Duration, Instant } };
+
+use crate::{
+    models::execution_outcome::ExecutionOutcome,
+    services::{ os_service::OSServiceImpl, shell_history::BashHistoryWriter },
+    traits::os_service::OSService,
+};
+
+#[test]
+fn capture_output_returns_stdout() {
+    let os = OSServiceImpl::default();
+
+    let output = os.capture_output("printf 'a\\nb\\n' | sort -r", Duration::from_secs(5)).unwrap();
+    assert_eq!(output, "b\na\n");
+}
+
+#[test]
+fn capture_output_fails_with_the_command() {
+    let os = OSServiceImpl::default();
+
+    assert!(os.capture_output("echo partial; exit 3", Duration::from_secs(5)).is_err());
+}
+
+#[test]
+fn capture_output_uses_the_configured_shell() {
+    let os = OSServiceImpl { shell: "bash".to_string(), ..OSServiceImpl::default() };
+
+    // `[[` is bash syntax
+    let output = os.capture_output("[[ a == a ]] && echo bash", Duration::from_secs(5)).unwrap();
+    assert_eq!(output, "bash\n");
+}
+
+#[test]
+fn capture_output_times_out() {
+    let os = OSServiceImpl::default();
+
+    let started = Instant::now();
+    assert!(os.capture_output("sleep 5", Duration::from_millis(100)).is_err());
+    assert!(started.elapsed() < Duration::from_secs(4));
+}
+
+#[test]
+fn the_recorded_command_goes_to_the_shell_history() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join(".bash_history");
+    let os = OSServiceImpl {
+        history: Some(Box::new(BashHistoryWriter { path: path.clone(), timestamps: false })),
+        ..OSServiceImpl::default()
+    };
+
+    let outcome = os.execute_command("test s3cr3t = s3cr3t", "test $TOKEN = s3cr3t")?;
+    assert!(outcome.success());
+    assert_eq!(std::fs::read_to_string(&path)?, "test $TOKEN = s3cr3t\n");
+
+    Ok(())
+}
+
+#[test]
+fn execution_outcome_reports_the_exit_code() {
+    let status = Command::new("sh").args(["-c", "exit 3"]).status().unwrap();
+    let outcome = ExecutionOutcome::from_status(status, Duration::from_millis(5));
+
+    assert_eq!(outcome.code, Some(3));
+    assert_eq!(outcome.signal, None);
+    assert!(!outcome.success());
+    assert_eq!(outcome.exit_code(), 3);
+}
+
+#[test]
+fn execution_outcome_reports_the_signal() {
+    let status = Command::new("sh").args(["-c", "kill -TERM $$"]).status().unwrap();
+    let outcome = ExecutionOutcome::from_status(status, Duration::default());
+
+    assert_eq!(outcome.code, None);
+    assert_eq!(outcome.signal, Some(15));
+    assert_eq!(outcome.exit_code(), 143);
+}
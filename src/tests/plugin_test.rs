@@ -0,0 +1,153 @@
+use std::{
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::{ Path, PathBuf },
+    time::{ Duration, Instant },
+};
+
+use crate::{
+    services::{
+        extension_registry::ExtensionRegistry,
+        plugin::{ discover, PluginExtension },
+        template::Placeholder,
+    },
+    traits::cmd_extension::{ CmdExtension, PlaceholderContext, PlaceholderValue },
+};
+
+/// Offers pods for `{pod}` in kubectl commands, and runs them against the dev context
+pub const KUBECTL_PLUGIN: &str = r#"
+request=$(cat)
+case "$request" in
+    *'"type":"handshake"'*) printf '%s\n' '{"protocol":1,"pattern":"^kubectl\\s"}' ;;
+    *'"type":"placeholder"'*'"placeholder":"pod"'*)
+        echo '{"values":[{"value":"api-1","description":"Running"},{"value":"web-2"}]}' ;;
+    *'"type":"command"'*'"command":"kubectl logs '*)
+        echo "$request" | sed 's/.*"command":"kubectl \([^"]*\)".*/{"command":"kubectl --context dev \1"}/' ;;
+    *) echo '{}' ;;
+esac
+"#;
+
+/// Writes an executable shell script to `dir`
+pub fn write_plugin(dir: &Path, name: &str, script: &str) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, format!("#!/bin/sh\n{}", script)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+fn named(name: &str) -> Placeholder {
+    Placeholder { name: Some(name.to_string()), ..Placeholder::default() }
+}
+
+#[test]
+fn plugins_offer_values_and_rewrite_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    let plugin = PluginExtension::new(
+        write_plugin(dir.path(), "cmd-plugin-kubectl", KUBECTL_PLUGIN),
+        Duration::from_secs(5)
+    );
+
+    assert_eq!(plugin.name(), "cmd-plugin-kubectl");
+    assert!(plugin.pattern().is_match("kubectl logs {pod}"));
+    assert!(!plugin.pattern().is_match("git checkout {branch}"));
+
+    let pod = named("pod");
+    let context = PlaceholderContext {
+        template: "kubectl logs {pod}",
+        placeholder: &pod,
+        before: "kubectl logs ".to_string(),
+    };
+    assert_eq!(
+        plugin.placeholder_values(&context),
+        Some(vec![PlaceholderValue::described("api-1", "Running"), PlaceholderValue::new("web-2")])
+    );
+    // A `{}` reply lets cmd offer its own values
+    let other = named("namespace");
+    assert_eq!(plugin.placeholder_values(&PlaceholderContext { placeholder: &other, ..context }), None);
+
+    assert_eq!(
+        plugin.rewrite("kubectl logs {pod}", "kubectl logs api-1"),
+        Some("kubectl --context dev logs api-1".to_string())
+    );
+    assert_eq!(plugin.rewrite("kubectl get {}", "kubectl get pods"), None);
+}
+
+#[test]
+fn plugins_are_asked_through_the_registry() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut registry = ExtensionRegistry::default();
+    registry.register(
+        Box::new(
+            PluginExtension::new(
+                write_plugin(dir.path(), "cmd-plugin-kubectl", KUBECTL_PLUGIN),
+                Duration::from_secs(5)
+            )
+        )
+    );
+
+    assert_eq!(
+        registry.rewrite("kubectl logs {pod}", "kubectl logs web-2"),
+        Some("kubectl --context dev logs web-2".to_string())
+    );
+    // Templates that do not match the handshake pattern are not sent to the plugin
+    assert_eq!(registry.rewrite("echo kubectl logs {}", "echo kubectl logs x"), None);
+}
+
+#[test]
+fn plugins_with_another_protocol_are_ignored() {
+    let dir = tempfile::tempdir().unwrap();
+    let script = r#"cat > /dev/null; echo '{"protocol":2,"pattern":".*"}'"#;
+    let plugin = PluginExtension::new(
+        write_plugin(dir.path(), "cmd-plugin-future", script),
+        Duration::from_secs(5)
+    );
+
+    assert!(!plugin.pattern().is_match("kubectl logs {pod}"));
+    assert!(!plugin.pattern().is_match(""));
+}
+
+#[test]
+fn slow_or_broken_plugins_are_ignored() {
+    let dir = tempfile::tempdir().unwrap();
+    let slow = PluginExtension::new(
+        write_plugin(dir.path(), "cmd-plugin-slow", "sleep 5"),
+        Duration::from_millis(100)
+    );
+
+    let started = Instant::now();
+    assert!(!slow.pattern().is_match("kubectl logs {pod}"));
+    assert!(started.elapsed() < Duration::from_secs(4));
+
+    let broken = PluginExtension::new(
+        write_plugin(dir.path(), "cmd-plugin-broken", "echo 'not json'"),
+        Duration::from_secs(5)
+    );
+    assert!(!broken.pattern().is_match("kubectl logs {pod}"));
+    assert_eq!(broken.rewrite("kubectl logs {pod}", "kubectl logs api-1"), None);
+
+    let missing = PluginExtension::new(dir.path().join("cmd-plugin-missing"), Duration::from_secs(5));
+    assert!(!missing.pattern().is_match("kubectl logs {pod}"));
+}
+
+#[test]
+fn plugins_are_discovered_on_the_path() {
+    let first = tempfile::tempdir().unwrap();
+    let second = tempfile::tempdir().unwrap();
+
+    let kubectl = write_plugin(first.path(), "cmd-plugin-kubectl", KUBECTL_PLUGIN);
+    // Shadowed by the one in the first directory
+    write_plugin(second.path(), "cmd-plugin-kubectl", KUBECTL_PLUGIN);
+    let aws = write_plugin(second.path(), "cmd-plugin-aws", KUBECTL_PLUGIN);
+    write_plugin(second.path(), "kubectl", KUBECTL_PLUGIN);
+    let not_executable = second.path().join("cmd-plugin-readme");
+    fs::write(&not_executable, "").unwrap();
+
+    let path = std::env::join_paths([first.path(), second.path(), Path::new("/does/not/exist")]).unwrap();
+    let configured = vec!["/opt/cmd/values".to_string()];
+
+    assert_eq!(
+        discover(Some(&path), &configured),
+        vec![PathBuf::from("/opt/cmd/values"), kubectl, aws]
+    );
+    assert!(discover(None, &[]).is_empty());
+}
@@ -0,0 +1,61 @@
+use crate::program::{ parse_programs, run_programs, Operator, Program };
+
+fn program(operator: Operator, args: &[&str]) -> Program {
+    Program {
+        operator,
+        args: args
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect(),
+    }
+}
+
+#[test]
+fn parse_programs_follows_quoting_rules() {
+    assert_eq!(parse_programs("grep \"foo bar\" 'it''s' a\\ b \"\\\"q\\\" \\n\" ''").unwrap(), vec![
+        program(Operator::Sequence, &["grep", "foo bar", "its", "a b", "\"q\" \\n", ""]),
+    ]);
+    assert_eq!(parse_programs("echo   'a  b'\t$HOME").unwrap(), vec![
+        program(Operator::Sequence, &["echo", "a  b", "$HOME"]),
+    ]);
+}
+
+#[test]
+fn parse_programs_splits_on_operators() {
+    assert_eq!(parse_programs("make&&make test || echo failed; echo 'a && b';").unwrap(), vec![
+        program(Operator::Sequence, &["make"]),
+        program(Operator::And, &["make", "test"]),
+        program(Operator::Or, &["echo", "failed"]),
+        program(Operator::Sequence, &["echo", "a && b"]),
+    ]);
+}
+
+#[test]
+fn parse_programs_rejects_what_needs_a_shell() {
+    for command in [
+        "grep foo | wc -l",
+        "echo hi > out.txt",
+        "sleep 1 &",
+        "(cd dir)",
+        "echo 'unterminated",
+        "make &&",
+        "&& make",
+    ] {
+        assert!(parse_programs(command).is_err(), "{}", command);
+    }
+}
+
+#[test]
+fn run_programs_short_circuits() {
+    let run = |command: &str| run_programs(parse_programs(command).unwrap()).unwrap().code();
+
+    assert_eq!(run("false && sh -c 'exit 7'"), Some(1));
+    assert_eq!(run("false || sh -c 'exit 7'"), Some(7));
+    assert_eq!(run("true || sh -c 'exit 7'"), Some(0));
+    assert_eq!(run("false; sh -c 'exit 4'"), Some(4));
+    assert_eq!(run("false && sh -c 'exit 7' || true"), Some(0));
+
+    // A program that cannot be started fails like in a shell
+    assert_eq!(run("cmd-does-not-exist || sh -c 'exit 7'"), Some(7));
+    assert_eq!(run("cmd-does-not-exist && true"), Some(127));
+}
@@ -0,0 +1,106 @@
+use std::{ fs, path::PathBuf };
+
+use crate::{
+    config::Config,
+    models::cmd_record::CmdSource,
+    services::project_commands::{ ProjectCommands, ProjectFile, PROJECT_FILE },
+};
+
+const REPO_FILE: &str = r#"
+[[commands]]
+command = "kubectl logs -f {pod}"
+description = "Follow the logs of a pod"
+tags = ["K8s", "logs", "k8s"]
+
+[[commands]]
+command = "make release"
+
+[providers]
+pod = "kubectl get pods -o name"
+"#;
+
+#[test]
+fn project_files_are_loaded_walking_up() {
+    let dir = tempfile::tempdir().unwrap();
+    let service = dir.path().join("services/api");
+    fs::create_dir_all(service.join("src")).unwrap();
+    fs::write(dir.path().join(PROJECT_FILE), REPO_FILE).unwrap();
+    fs::write(
+        service.join(PROJECT_FILE),
+        "[[commands]]\ncommand = \"make release\"\ndescription = \"Release the API\"\n"
+    ).unwrap();
+    // Not valid, so skipped
+    fs::write(dir.path().join("services").join(PROJECT_FILE), "[[commands]]\ndescription = 1\n").unwrap();
+
+    let project = ProjectCommands::load(&service.join("src"), &[dir.path().to_path_buf()]);
+    assert_eq!(
+        project.files
+            .iter()
+            .map(|file| file.path.clone())
+            .collect::<Vec<_>>(),
+        vec![service.join(PROJECT_FILE), dir.path().join(PROJECT_FILE)]
+    );
+    // Only the directory listed is trusted, not the ones below it
+    assert_eq!(
+        project.files
+            .iter()
+            .map(|file| file.trusted)
+            .collect::<Vec<_>>(),
+        vec![false, true]
+    );
+
+    // The closest file describes the commands they both have
+    let records = project.records();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].command, "make release");
+    assert_eq!(records[0].description, Some("Release the API".to_string()));
+    assert_eq!(records[1].command, "kubectl logs -f {pod}");
+    assert_eq!(records[1].tags, vec!["k8s", "logs"]);
+    assert!(records.iter().all(|record| record.id == 0));
+    // Trusted as the file they come from
+    assert_eq!(records[0].source, CmdSource::Project { trusted: false });
+    assert_eq!(records[1].source, CmdSource::Project { trusted: true });
+    assert!(!project.trusts("make release"));
+    assert!(project.trusts("kubectl logs -f {pod}"));
+    assert!(project.trusts("git log"));
+}
+
+#[test]
+fn project_providers_only_apply_to_their_commands() {
+    let mut file = ProjectFile::parse(PathBuf::from(PROJECT_FILE), REPO_FILE).unwrap();
+    file.trusted = true;
+    let project = ProjectCommands { files: vec![file] };
+
+    assert_eq!(
+        project.provider("kubectl logs -f {pod}", "pod"),
+        Some("kubectl get pods -o name".to_string())
+    );
+    assert_eq!(project.provider("kubectl logs -f {pod}", "namespace"), None);
+    assert_eq!(project.provider("kubectl describe {pod}", "pod"), None);
+
+    assert!(ProjectFile::parse(PathBuf::from(PROJECT_FILE), "commands = 1").is_err());
+}
+
+#[test]
+fn project_providers_only_run_once_trusted() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join(PROJECT_FILE), REPO_FILE).unwrap();
+
+    // Their commands are still offered
+    let untrusted = ProjectCommands::load(dir.path(), &[]);
+    assert_eq!(untrusted.records().len(), 2);
+    assert_eq!(untrusted.provider("kubectl logs -f {pod}", "pod"), None);
+
+    // The same directory, written another way
+    let same_dir = dir.path().join("..").join(dir.path().file_name().unwrap());
+    let trusted = ProjectCommands::load(dir.path(), &[same_dir]);
+    assert_eq!(
+        trusted.provider("kubectl logs -f {pod}", "pod"),
+        Some("kubectl get pods -o name".to_string())
+    );
+
+    let config = Config::parse("trusted_projects = [\"~/work/api\", \"/srv/app\"]").unwrap();
+    assert!(config.trusted_projects()[0].ends_with("work/api"));
+    assert!(!config.trusted_projects()[0].starts_with("~"));
+    assert_eq!(config.trusted_projects()[1], PathBuf::from("/srv/app"));
+}
@@ -0,0 +1,35 @@
+use crate::{
+    config::Config,
+    models::{ cmd_record::CmdRecord, history_record::timestamp_now },
+    services::ranking::{ FrecencyRanking, Ranking, UsedTimesRanking },
+    traits::ranking::RankingStrategy,
+};
+
+fn record(command: &str, used_times: usize, last_used: Option<i64>) -> CmdRecord {
+    CmdRecord {
+        command: command.to_string(),
+        used_times,
+        last_used,
+        ..CmdRecord::default()
+    }
+}
+
+#[test]
+fn frecency_prefers_recent_commands() {
+    let now = timestamp_now();
+    let year_ago = record("make old", 200, Some(now - 365 * 24 * 60 * 60));
+    let yesterday = record("make new", 30, Some(now - 60 * 60 * 2));
+
+    let frecency = FrecencyRanking::default();
+    assert!(frecency.score(&yesterday, now) > frecency.score(&year_ago, now));
+
+    let used_times = UsedTimesRanking::default();
+    assert!(used_times.score(&yesterday, now) < used_times.score(&year_ago, now));
+}
+
+#[test]
+fn ranking_is_read_from_config() {
+    assert_eq!(Config::default().ranking, Ranking::Frecency);
+    assert_eq!(Config::parse("ranking = \"used_times\"").unwrap().ranking, Ranking::UsedTimes);
+    assert!(Config::parse("ranking = \"alphabetical\"").is_err());
+}
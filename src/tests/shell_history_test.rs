@@ -0,0 +1,128 @@
+use std::{ path::PathBuf, time::Duration };
+
+use crate::{
+    config::Config,
+    services::shell_history::{
+        append,
+        is_zsh_extended,
+        has_bash_timestamps,
+        BashHistoryWriter,
+        FishHistoryWriter,
+        HistoryEntry,
+        ShellHistory,
+        ZshHistoryWriter,
+    },
+    traits::history_writer::HistoryWriter,
+};
+
+fn text(writer: &dyn HistoryWriter, command: &str) -> String {
+    String::from_utf8(writer.format(command, 1700000000, Duration::from_millis(2500))).unwrap()
+}
+
+#[test]
+fn bash_history_format() {
+    let plain = BashHistoryWriter { path: PathBuf::new(), timestamps: false };
+    assert_eq!(text(&plain, "ls -l"), "ls -l\n");
+
+    let timestamps = BashHistoryWriter { path: PathBuf::new(), timestamps: true };
+    assert_eq!(text(&timestamps, "ls -l"), "#1700000000\nls -l\n");
+}
+
+#[test]
+fn zsh_history_format() {
+    let plain = ZshHistoryWriter { path: PathBuf::new(), extended: false };
+    assert_eq!(text(&plain, "echo a &&\necho b"), "echo a &&\\\necho b\n");
+
+    let extended = ZshHistoryWriter { path: PathBuf::new(), extended: true };
+    assert_eq!(text(&extended, "git status"), ": 1700000000:2;git status\n");
+
+    // 'é' is 0xc3 0xa9 and is kept as is, '→' is 0xe2 0x86 0x92 and needs two escapes
+    assert_eq!(plain.format("é→", 0, Duration::default()), vec![
+        0xc3, 0xa9, 0xe2, 0x83, 0xa6, 0x83, 0xb2, b'\n'
+    ]);
+}
+
+#[test]
+fn fish_history_format() {
+    let fish = FishHistoryWriter { path: PathBuf::new() };
+    assert_eq!(
+        text(&fish, "printf 'a\\tb'\necho done"),
+        "- cmd: printf 'a\\\\tb'\\necho done\n  when: 1700000000\n"
+    );
+}
+
+#[test]
+fn history_formats_are_detected() {
+    assert_eq!(ShellHistory::detect("/usr/local/bin/fish"), ShellHistory::Fish);
+    assert_eq!(ShellHistory::detect("/bin/zsh"), ShellHistory::Zsh);
+    assert_eq!(ShellHistory::detect("bash"), ShellHistory::Bash);
+    assert_eq!(ShellHistory::detect("/bin/tcsh"), ShellHistory::None);
+    assert_eq!(ShellHistory::detect(""), ShellHistory::None);
+
+    assert!(is_zsh_extended("ls\n: 1699999999:0;git status\n"));
+    assert!(!is_zsh_extended("ls\n: not a timestamp;\n"));
+    assert!(has_bash_timestamps("#1699999999\nls\n"));
+    assert!(!has_bash_timestamps("# a comment\nls\n"));
+}
+
+#[test]
+fn history_is_appended_to_the_file() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join(".zsh_history");
+    let writer = ZshHistoryWriter { path: path.clone(), extended: true };
+
+    append(&writer, "ls", 1, Duration::from_secs(3))?;
+    append(&writer, "pwd", 5, Duration::default())?;
+
+    assert_eq!(std::fs::read_to_string(&path)?, ": 1:3;ls\n: 5:0;pwd\n");
+    Ok(())
+}
+
+#[test]
+fn shell_history_is_read_from_config() {
+    let config = Config::parse("shell_history = \"none\"\nshell_history_file = \"~/h\"").unwrap();
+    assert_eq!(config.shell_history, ShellHistory::None);
+    assert_eq!(config.shell_history_file, Some("~/h".to_string()));
+    assert!(config.shell_history.build(None).is_none());
+
+    let config = Config::parse("shell_history = \"zsh_extended\"").unwrap();
+    assert_eq!(config.shell_history, ShellHistory::ZshExtended);
+    assert_eq!(Config::default().shell_history, ShellHistory::Auto);
+}
+
+fn commands(entries: Vec<HistoryEntry>) -> Vec<(String, Option<i64>)> {
+    entries
+        .into_iter()
+        .map(|entry| (entry.command, entry.timestamp))
+        .collect()
+}
+
+#[test]
+fn zsh_history_is_parsed() {
+    let mut content = b": 1700000000:0;git status\nls -l\n: 1700000005:3;echo a &&\\\necho b\n".to_vec();
+    // What the writer produces reads back the same
+    let writer = ZshHistoryWriter { path: PathBuf::new(), extended: true };
+    content.extend(writer.format("echo é→", 1700000009, Duration::default()));
+
+    assert_eq!(commands(ShellHistory::Zsh.parse(&content)), vec![
+        ("git status".to_string(), Some(1700000000)),
+        ("ls -l".to_string(), None),
+        ("echo a &&\necho b".to_string(), Some(1700000005)),
+        ("echo é→".to_string(), Some(1700000009))
+    ]);
+}
+
+#[test]
+fn bash_and_fish_history_are_parsed() {
+    let bash = "#1700000000\ngit status\nls -l\n\n";
+    assert_eq!(commands(ShellHistory::Bash.parse(bash.as_bytes())), vec![
+        ("git status".to_string(), Some(1700000000)),
+        ("ls -l".to_string(), None)
+    ]);
+
+    let fish = "- cmd: printf 'a\\\\tb'\\necho done\n  when: 1700000000\n  paths:\n    - /tmp\n- cmd: ls\n";
+    assert_eq!(commands(ShellHistory::Fish.parse(fish.as_bytes())), vec![
+        ("printf 'a\\tb'\necho done".to_string(), Some(1700000000)),
+        ("ls".to_string(), None)
+    ]);
+}
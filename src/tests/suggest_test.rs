@@ -0,0 +1,63 @@
+use crate::services::{ shell_history::HistoryEntry, suggest::{ suggest, Suggestion } };
+
+fn history(commands: &[&str]) -> Vec<HistoryEntry> {
+    commands
+        .iter()
+        .map(|command| HistoryEntry { command: command.to_string(), timestamp: None })
+        .collect()
+}
+
+fn templates(suggestions: &[Suggestion]) -> Vec<&str> {
+    suggestions
+        .iter()
+        .map(|suggestion| suggestion.template.as_str())
+        .collect()
+}
+
+#[test]
+fn similar_commands_become_a_template() {
+    let entries = history(&[
+        "kubectl logs -f api-1 -n prod",
+        "git status",
+        "kubectl logs -f web-2 -n prod",
+        "kubectl logs -f api-1 -n prod",
+        "git commit -m 'fix the build'",
+        "git commit -m 'add a test'",
+    ]);
+
+    let suggestions = suggest(&entries, &[], |_| false);
+
+    assert_eq!(templates(&suggestions), vec!["kubectl logs -f {} -n prod", "git commit -m {}"]);
+    assert_eq!(suggestions[0].uses, 3);
+    assert_eq!(suggestions[0].examples, vec![
+        "kubectl logs -f api-1 -n prod",
+        "kubectl logs -f web-2 -n prod"
+    ]);
+}
+
+#[test]
+fn frequent_and_long_commands_are_suggested() {
+    let long = "docker run --rm -it -v $(pwd):/work -w /work ghcr.io/some/toolchain:latest make all";
+    let entries = history(&["make", "make", "make", "git push", "ls -l", "ls -l", "ls -l", long]);
+
+    let suggestions = suggest(&entries, &["ls".to_string()], |_| false);
+
+    assert_eq!(templates(&suggestions), vec!["make", long]);
+}
+
+#[test]
+fn saved_commands_are_not_suggested() {
+    let entries = history(&[
+        "find . -name '*.rs' -exec wc -l {} +",
+        "find . -name '*.rs' -exec wc -l {} +",
+        "find . -name '*.rs' -exec wc -l {} +",
+        "cargo test",
+        "cargo test",
+        "cargo test",
+    ]);
+
+    let suggestions = suggest(&entries, &[], |command| command == "cargo test");
+
+    // Literal braces are escaped, as they are not placeholders
+    assert_eq!(templates(&suggestions), vec!["find . -name '*.rs' -exec wc -l {{}} +"]);
+}
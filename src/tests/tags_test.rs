@@ -0,0 +1,144 @@
+use rusqlite::Connection;
+
+use crate::{
+    error::CmdError,
+    models::history_record::HistoryRecord,
+    services::{
+        cmd_service_sql::CmdServiceSQL,
+        controller::{ parse_tag, Controller },
+        ranking::Ranking,
+    },
+    traits::cmd_service::{ CmdService, HistoryFilters, SearchFilters, SearchFiltersBuilder },
+    log_debug,
+};
+
+pub fn initialize() {
+    let _ = env_logger::builder().is_test(true).filter_level(log::LevelFilter::Debug).try_init();
+}
+
+fn tagged(cmd_service: &mut CmdServiceSQL, tags: &[&str]) -> Vec<String> {
+    let tags = tags
+        .iter()
+        .map(|tag| tag.to_string())
+        .collect::<Vec<_>>();
+    let commands = cmd_service.get_commands(SearchFiltersBuilder::default().tags(tags).build().unwrap());
+    log_debug!("Tagged: {:?}", commands);
+    commands
+        .into_iter()
+        .map(|cmd| cmd.command)
+        .collect()
+}
+
+#[test]
+fn tags_filter_commands() -> Result<(), CmdError> {
+    initialize();
+    let mut cmd_service = CmdServiceSQL::build_cmd_service(Some(Connection::open_in_memory()?))?;
+
+    cmd_service.add_command("kubectl rollout restart deployment".to_string())?;
+    cmd_service.add_command("kubectl get pods".to_string())?;
+    cmd_service.add_command("git log".to_string())?;
+
+    let commands = cmd_service.get_commands(SearchFilters::default());
+    cmd_service.add_tag(commands[0].id, "k8s")?;
+    cmd_service.add_tag(commands[0].id, "deploy")?;
+    cmd_service.add_tag(commands[1].id, "k8s")?;
+    // Tagging twice is a no-op
+    cmd_service.add_tag(commands[1].id, "k8s")?;
+
+    assert_eq!(tagged(&mut cmd_service, &["k8s"]), vec![
+        "kubectl rollout restart deployment",
+        "kubectl get pods",
+    ]);
+    assert_eq!(tagged(&mut cmd_service, &["k8s", "deploy"]), vec![
+        "kubectl rollout restart deployment",
+    ]);
+    assert!(tagged(&mut cmd_service, &["git"]).is_empty());
+
+    let record = cmd_service.get_commands(SearchFilters::default()).remove(0);
+    assert_eq!(record.tags, vec!["deploy", "k8s"]);
+    assert_eq!(cmd_service.get_tags()?, vec![("deploy".to_string(), 1), ("k8s".to_string(), 2)]);
+
+    cmd_service.remove_tag(commands[0].id, "deploy")?;
+    assert_eq!(cmd_service.get_tags()?, vec![("k8s".to_string(), 2)]);
+
+    // Deleting a command drops its tags
+    cmd_service.delete_command(commands[1].clone())?;
+    assert_eq!(cmd_service.get_tags()?, vec![("k8s".to_string(), 1)]);
+
+    Ok(())
+}
+
+#[test]
+fn descriptions_and_tags_are_searchable() -> Result<(), CmdError> {
+    initialize();
+    let mut cmd_service = CmdServiceSQL::build_cmd_service(Some(Connection::open_in_memory()?))?;
+
+    cmd_service.add_command("kubectl rollout restart deployment/api".to_string())?;
+    cmd_service.add_command("git log".to_string())?;
+
+    let mut record = cmd_service.get_commands(SearchFilters::default()).remove(0);
+    record.description = Some("Bounce the API pods".to_string());
+    cmd_service.update_command(record.clone())?;
+    cmd_service.add_tag(record.id, "prod")?;
+
+    let stored = cmd_service.get_commands(SearchFilters::default()).remove(0);
+    assert_eq!(stored.description, Some("Bounce the API pods".to_string()));
+    assert_eq!(stored.display(), "kubectl rollout restart deployment/api  # Bounce the API pods");
+
+    for text in ["bounce pods", "prod restart"] {
+        let results = cmd_service.search_full_text(text)?;
+        assert_eq!(results.len(), 1, "{}", text);
+        assert_eq!(results[0].0.id, record.id);
+    }
+
+    cmd_service.remove_tag(record.id, "prod")?;
+    assert!(cmd_service.search_full_text("prod")?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn using_a_command_through_an_alias_keeps_what_is_known_about_it() -> Result<(), CmdError> {
+    initialize();
+    let mut cmd_service = CmdServiceSQL::build_cmd_service(Some(Connection::open_in_memory()?))?;
+
+    cmd_service.add_command("kubectl logs {pod}".to_string())?;
+    cmd_service.add_command("kubectl logs api".to_string())?;
+    let mut aliased = cmd_service
+        .get_commands(SearchFiltersBuilder::default().command("kubectl logs api".to_string()).build().unwrap())
+        .remove(0);
+    aliased.description = Some("Logs of the API".to_string());
+    cmd_service.update_command(aliased.clone())?;
+    cmd_service.add_tag(aliased.id, "k8s")?;
+    cmd_service.add_history(HistoryRecord {
+        cmd_id: Some(aliased.id),
+        command: aliased.command.clone(),
+        ..HistoryRecord::default()
+    })?;
+
+    let mut controller = Controller {
+        all: cmd_service.clone(),
+        used: cmd_service.clone(),
+        ranking: Ranking::UsedTimes.build(),
+        dry_run: false,
+    };
+    let template = controller.find_command("kubectl logs {pod}").unwrap();
+    controller.add_used_command(template, Some("kubectl logs api".to_string()))?;
+
+    let stored = controller.find_command("kubectl logs api").unwrap();
+    assert_eq!(stored.id, aliased.id);
+    assert_eq!(stored.used_times, 1);
+    assert_eq!(stored.description, Some("Logs of the API".to_string()));
+    assert_eq!(stored.tags, vec!["k8s"]);
+    assert_eq!(cmd_service.get_history(HistoryFilters::default())?[0].cmd_id, Some(aliased.id));
+
+    Ok(())
+}
+
+#[test]
+fn tags_are_single_lowercase_words() {
+    assert_eq!(parse_tag(" K8s ").unwrap(), "k8s");
+    assert!(parse_tag("").is_err());
+    assert!(parse_tag("two words").is_err());
+    assert!(parse_tag("a,b").is_err());
+}
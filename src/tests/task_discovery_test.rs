@@ -0,0 +1,164 @@
+use std::{ fs, path::Path };
+
+use crate::{
+    models::cmd_record::CmdSource,
+    services::task_discovery::{ cargo_tasks, just_tasks, make_tasks, npm_tasks, Task, TaskDiscovery },
+};
+
+fn commands(tasks: Vec<Task>) -> Vec<String> {
+    tasks
+        .into_iter()
+        .map(|task| task.command)
+        .collect()
+}
+
+#[test]
+fn makefile_targets_are_tasks() {
+    let makefile = "\
+CC := gcc
+FILES = a:b
+.PHONY: build test
+%.o: %.c
+\t$(CC) -c $<
+build: main.o ## Build the binary
+\t$(CC) -o app main.o
+test lint:
+\t./run-tests
+$(OUT): build
+";
+    let tasks = make_tasks(makefile, Path::new(""), "Makefile");
+
+    assert_eq!(commands(tasks.clone()), vec!["make build", "make test", "make lint"]);
+    assert_eq!(tasks[0].description, "Makefile: Build the binary");
+    assert_eq!(tasks[1].description, "Makefile");
+
+    let nested = make_tasks("docs:\n", Path::new("site"), "Makefile");
+    assert_eq!(nested[0].command, "make -C site docs");
+    assert_eq!(nested[0].description, "site/Makefile");
+}
+
+#[test]
+fn package_scripts_are_tasks() {
+    let package = r#"{
+        "name": "web",
+        "scripts": { "test": "vitest run", "pretest": "tsc", "build:prod": "vite build", "dev": "vite" }
+    }"#;
+    let tasks = npm_tasks(package, Path::new(""), "npm");
+
+    assert_eq!(commands(tasks.clone()), vec!["npm run build:prod", "npm run dev", "npm run test"]);
+    assert_eq!(tasks[2].description, "package.json: vitest run");
+
+    assert_eq!(commands(npm_tasks(package, Path::new("web"), "pnpm"))[1], "pnpm --dir web run dev");
+    assert_eq!(commands(npm_tasks(package, Path::new("web"), "yarn"))[1], "yarn --cwd web run dev");
+    assert!(npm_tasks("{ not json", Path::new(""), "npm").is_empty());
+}
+
+#[test]
+fn cargo_binaries_examples_and_members_are_tasks() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("src/bin/server")).unwrap();
+    fs::create_dir_all(root.join("examples")).unwrap();
+    fs::create_dir_all(root.join("crates/core")).unwrap();
+    fs::write(root.join("src/main.rs"), "").unwrap();
+    fs::write(root.join("src/bin/migrate.rs"), "").unwrap();
+    fs::write(root.join("src/bin/server/main.rs"), "").unwrap();
+    fs::write(root.join("examples/demo.rs"), "").unwrap();
+    fs::write(root.join("crates/core/Cargo.toml"), "[package]\nname = \"app-core\"\n").unwrap();
+
+    let manifest = "\
+[package]
+name = \"app\"
+
+[[bin]]
+name = \"tool\"
+path = \"tools/main.rs\"
+
+[workspace]
+members = [\"crates/*\"]
+";
+    assert_eq!(
+        commands(cargo_tasks(manifest, root, Path::new(""))),
+        vec![
+            "cargo run --bin tool",
+            "cargo run --bin app",
+            "cargo run --bin migrate",
+            "cargo run --bin server",
+            "cargo run --example demo",
+            "cargo build -p app-core",
+            "cargo test -p app-core"
+        ]
+    );
+
+    let nested = cargo_tasks("[package]\nname = \"app-core\"\n", &root.join("crates/core"), Path::new("crates/core"));
+    assert!(nested.is_empty());
+    let nested = cargo_tasks(manifest, root, Path::new("app"));
+    assert_eq!(nested[0].command, "cargo run --manifest-path app/Cargo.toml --bin tool");
+}
+
+#[test]
+fn justfile_recipes_are_tasks_with_placeholders() {
+    let justfile = "\
+set shell := [\"bash\", \"-c\"]
+version := \"1.0\"
+alias b := build
+
+# Build everything
+build:
+    cargo build
+
+# Deploy to an environment
+deploy env='dev' region=\"eu west\" tag=version: build
+    ./deploy {{env}}
+
+@run +args:
+    ./app {{args}}
+
+[private]
+helper:
+    echo
+
+_hidden *flags:
+    echo
+";
+    let tasks = just_tasks(justfile, Path::new(""), "justfile");
+
+    assert_eq!(
+        commands(tasks.clone()),
+        vec!["just build", "just deploy {env:dev} {region:eu west} {tag}", "just run {args}"]
+    );
+    assert_eq!(tasks[0].description, "justfile: Build everything");
+    assert_eq!(tasks[2].description, "justfile");
+
+    assert_eq!(
+        commands(just_tasks("test:\n", Path::new("api"), "justfile")),
+        vec!["just --justfile api/justfile test"]
+    );
+}
+
+#[test]
+fn tasks_are_discovered_in_the_directory_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("web/node_modules/lib")).unwrap();
+    fs::create_dir_all(root.join("a/b/c")).unwrap();
+    fs::write(root.join("Makefile"), "test:\n").unwrap();
+    fs::write(root.join("web/package.json"), r#"{"scripts": {"dev": "vite"}}"#).unwrap();
+    fs::write(root.join("web/yarn.lock"), "").unwrap();
+    fs::write(root.join("web/node_modules/lib/package.json"), r#"{"scripts": {"x": "y"}}"#).unwrap();
+    fs::write(root.join("a/b/justfile"), "lint:\n").unwrap();
+    // Too deep
+    fs::write(root.join("a/b/c/Makefile"), "deep:\n").unwrap();
+
+    let records = TaskDiscovery::new(root.to_path_buf()).tasks();
+    assert_eq!(
+        records
+            .iter()
+            .map(|record| record.command.as_str())
+            .collect::<Vec<_>>(),
+        vec!["make test", "just --justfile a/b/justfile lint", "yarn --cwd web run dev"]
+    );
+    assert!(records.iter().all(|record| record.source == CmdSource::Discovered));
+
+    assert!(TaskDiscovery::default().tasks().is_empty());
+}
@@ -0,0 +1,103 @@
+use crate::services::template::{ escape, Placeholder, Template };
+
+fn values(values: &[&str]) -> Vec<String> {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect()
+}
+
+#[test]
+fn anonymous_placeholders_are_filled_in_order() {
+    let template = Template::parse("git commit -m {} --author {}");
+
+    assert_eq!(template.placeholders.len(), 2);
+    assert_eq!(template.render(&values(&["wip", "me"])), "git commit -m wip --author me");
+}
+
+#[test]
+fn named_placeholders_are_filled_once() {
+    let template = Template::parse("git checkout {branch} && git push origin {branch}");
+
+    assert_eq!(template.placeholders, vec![Placeholder {
+        name: Some("branch".to_string()),
+        default: None,
+        choices: Vec::new(),
+        provider: None,
+    }]);
+    assert_eq!(
+        template.render(&values(&["main"])),
+        "git checkout main && git push origin main"
+    );
+}
+
+#[test]
+fn placeholders_can_have_defaults() {
+    let template = Template::parse("python -m http.server {port:8080} --bind {host:}");
+
+    assert_eq!(template.placeholders[0].default, Some("8080".to_string()));
+    assert_eq!(template.placeholders[1].default, Some("".to_string()));
+    assert_eq!(template.render(&[]), "python -m http.server 8080 --bind ");
+
+    // A later occurrence can give the default
+    let template = Template::parse("echo {port} {port:80}");
+    assert_eq!(template.render(&[]), "echo 80 80");
+}
+
+#[test]
+fn literal_braces_are_kept() {
+    let template = Template::parse("find . -name {name} -exec rm {{}} \\;");
+    assert_eq!(template.placeholders.len(), 1);
+    assert_eq!(template.render(&values(&["*.tmp"])), "find . -name *.tmp -exec rm {} \\;");
+
+    // Not placeholders: shell parameters and brace groups that are not a name
+    for text in ["echo ${HOME}", "awk '{print $1}'", "cp file{,.bak}", "echo {"] {
+        let template = Template::parse(text);
+        assert!(!template.has_placeholders(), "{}", text);
+        assert_eq!(template.render(&[]), text);
+    }
+}
+
+#[test]
+fn escaped_text_renders_back() {
+    for text in ["find . -exec rm {} \\;", "echo ${HOME} {x}", "}{"] {
+        let template = Template::parse(&escape(text));
+        assert!(!template.has_placeholders(), "{}", text);
+        assert_eq!(template.render(&[]), text);
+    }
+}
+
+#[test]
+fn choice_placeholders_list_their_options() {
+    let template = Template::parse("deploy --env {env:dev | staging|prod} && notify {env}");
+
+    assert_eq!(template.placeholders.len(), 1);
+    assert_eq!(template.placeholders[0].choices, vec!["dev", "staging", "prod"]);
+    assert_eq!(template.placeholders[0].default, None);
+    assert_eq!(template.render(&values(&["prod"])), "deploy --env prod && notify prod");
+}
+
+#[test]
+fn provider_placeholders_keep_their_command() {
+    let template = Template::parse("kubectl logs {pod:$(kubectl get pods -o name | grep api)} -f");
+
+    assert_eq!(
+        template.placeholders[0].provider,
+        Some("kubectl get pods -o name | grep api".to_string())
+    );
+    assert!(template.placeholders[0].choices.is_empty());
+    assert_eq!(template.render(&values(&["pod/api-1"])), "kubectl logs pod/api-1 -f");
+}
+
+#[test]
+fn braces_in_quotes_and_substitutions_do_not_close_placeholders() {
+    let template = Template::parse("echo {x:$(awk '{print $1}' f)} {msg:\"}\"}");
+
+    assert_eq!(template.placeholders.len(), 2);
+    assert_eq!(template.placeholders[0].provider, Some("awk '{print $1}' f".to_string()));
+    assert_eq!(template.placeholders[1].default, Some("\"}\"".to_string()));
+
+    let template = Template::parse("echo ${X:-$(echo })} {}");
+    assert_eq!(template.placeholders.len(), 1);
+    assert_eq!(template.render(&values(&["y"])), "echo ${X:-$(echo })} y");
+}
@@ -1,9 +1,68 @@
-use crate::{ Deps, error::CmdError, models::cmd_record::CmdRecord };
+use regex::Regex;
 
+use crate::{
+    error::CmdError,
+    models::execution_outcome::ExecutionOutcome,
+    services::template::Placeholder,
+};
+
+/// Where a placeholder is, so that extensions can tell what values make sense for it
+pub struct PlaceholderContext<'a> {
+    /// The saved command the placeholder belongs to
+    pub template: &'a str,
+    pub placeholder: &'a Placeholder,
+    /// The command up to the placeholder, with other placeholders left as `{}`
+    pub before: String,
+}
+
+/// A value offered for a placeholder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaceholderValue {
+    pub value: String,
+    /// Shown next to the value, e.g. the subject of a commit
+    pub description: Option<String>,
+}
+
+impl PlaceholderValue {
+    pub fn new(value: impl Into<String>) -> Self {
+        PlaceholderValue { value: value.into(), description: None }
+    }
+
+    pub fn described(value: impl Into<String>, description: impl Into<String>) -> Self {
+        PlaceholderValue { value: value.into(), description: Some(description.into()) }
+    }
+
+    pub fn display(&self) -> String {
+        match &self.description {
+            Some(description) if !description.is_empty() => format!("{}  # {}", self.value, description),
+            _ => self.value.clone(),
+        }
+    }
+}
+
+/// Knows about some commands: it can offer values for their placeholders, or run them itself.
 pub trait CmdExtension {
-    fn handle_if_match(
-        self,
-        pattern: &Option<CmdRecord>,
-        deps: &mut Deps
-    ) -> Option<Result<(), CmdError>>;
-}
\ No newline at end of file
+    fn name(&self) -> &str;
+
+    /// The templates the extension is asked about
+    fn pattern(&self) -> &Regex;
+
+    /// Values to offer for the placeholder, or `None` when the extension has nothing to add
+    fn placeholder_values(&self, _context: &PlaceholderContext) -> Option<Vec<PlaceholderValue>> {
+        None
+    }
+
+    /// A command to run in place of the filled one, or `None` to keep it
+    fn rewrite(&self, _template: &str, _command: &str) -> Option<String> {
+        None
+    }
+
+    /// Runs the filled command in place of cmd, or returns `None` to let cmd run it
+    fn execute(
+        &self,
+        _template: &str,
+        _command: &str
+    ) -> Option<Result<ExecutionOutcome, CmdError>> {
+        None
+    }
+}
@@ -1,13 +1,62 @@
+use std::collections::HashMap;
+
 use derive_builder::Builder;
 
-use crate::{ models::cmd_record::CmdRecord, error::CmdError };
+use crate::{
+    models::{ cmd_record::CmdRecord, history_record::HistoryRecord },
+    error::CmdError,
+    services::location::Location,
+};
+
+/// How `SearchFilters::command` is compared to the stored commands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    Exact,
+    Prefix,
+    #[default]
+    Substring,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchOrder {
+    /// Most used first
+    UsedTimes,
+    /// Most recently used first, never used last
+    LastUsed,
+    /// Alphabetically by command
+    Command,
+}
 
 #[derive(Default, Builder)]
 #[builder(setter(into), pattern = "owned", default)]
 pub struct SearchFilters {
     pub id: Option<usize>,
     pub command: Option<String>,
+    pub match_mode: MatchMode,
     pub used: bool,
+    /// Only commands that were executed in this location's project
+    pub location: Option<Location>,
+    /// Only commands that have every one of these tags
+    pub tags: Vec<String>,
+    /// Storage order when not set
+    pub order: Option<SearchOrder>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Default, Builder)]
+#[builder(setter(into, strip_option), pattern = "owned", default)]
+pub struct HistoryFilters {
+    /// Only executions whose command line contains this text
+    pub command: Option<String>,
+    /// Only executions that ran in this directory or below it
+    pub cwd: Option<String>,
+    /// Inclusive lower bound, as a date understood by SQLite (e.g. `2022-10-04`)
+    pub since: Option<String>,
+    /// Inclusive upper bound, as a date understood by SQLite (e.g. `2022-10-04`)
+    pub until: Option<String>,
+    pub failed: bool,
+    pub limit: Option<usize>,
 }
 
 pub trait CmdService {
@@ -17,8 +66,33 @@ pub trait CmdService {
     fn is_record_present(self: &Self, record: &CmdRecord) -> bool;
     fn reset_commands(self: &mut Self, updated_commands: Vec<CmdRecord>) -> Result<(), CmdError>;
     fn get_commands(self: &mut Self, filter: SearchFilters) -> Vec<CmdRecord>;
+    /// Full-text search, best match first. Each command comes with its relevance, higher is better.
+    fn search_full_text(&mut self, text: &str) -> Result<Vec<(CmdRecord, f64)>, CmdError>;
     fn get_file_name(self: &Self) -> String;
     fn clear_commands(self: &Self) -> Result<(), CmdError>;
     fn debug(self: &Self);
     fn delete_command(self: &mut Self, command: CmdRecord) -> Result<(), CmdError>;
+    fn add_history(&mut self, entry: HistoryRecord) -> Result<(), CmdError>;
+    fn get_history(&mut self, filter: HistoryFilters) -> Result<Vec<HistoryRecord>, CmdError>;
+    /// Number of executions of each command (by id) in the location's project
+    fn get_location_usage(
+        &mut self,
+        location: &Location
+    ) -> Result<HashMap<usize, usize>, CmdError>;
+    fn add_tag(&mut self, cmd_id: usize, tag: &str) -> Result<(), CmdError>;
+    fn remove_tag(&mut self, cmd_id: usize, tag: &str) -> Result<(), CmdError>;
+    /// Every tag with the number of commands that have it, by name
+    fn get_tags(&mut self) -> Result<Vec<(String, usize)>, CmdError>;
+    fn add_placeholder_value(
+        &mut self,
+        template: &str,
+        name: &str,
+        value: &str
+    ) -> Result<(), CmdError>;
+    /// Values given before to the placeholder of the template, most used first
+    fn get_placeholder_values(
+        &mut self,
+        template: &str,
+        name: &str
+    ) -> Result<Vec<String>, CmdError>;
 }
\ No newline at end of file
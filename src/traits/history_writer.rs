@@ -0,0 +1,11 @@
+use std::{ path::Path, time::Duration };
+
+/// Writes executed commands to a shell's history file, so that they can be recalled from the
+/// shell itself.
+pub trait HistoryWriter {
+    fn path(&self) -> &Path;
+
+    /// The bytes appended to the history file for one command, including the trailing newline.
+    /// `started_at` is in seconds since the UNIX epoch.
+    fn format(&self, command: &str, started_at: i64, duration: Duration) -> Vec<u8>;
+}
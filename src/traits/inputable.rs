@@ -4,11 +4,28 @@ use mockall::automock;
 pub trait Inputable {
     fn get_input(self: &Self, prompt: Option<String>) -> String;
 
+    /// Like `get_input`, returning `default` when the user leaves it empty
+    fn get_input_with_default(&self, prompt: Option<String>, default: String) -> String;
+
+    /// Like `get_input`, but the user can leave it empty
+    fn get_optional_input(&self, prompt: Option<String>) -> Option<String>;
+
     fn select_option(
         self: &Self,
         options: &Vec<String>,
         maybe_prompt: Option<String>
     ) -> Option<usize>;
 
+    /// Like `select_option`, with the chars at the given positions of each option highlighted
+    fn select_highlighted(
+        &self,
+        options: &[String],
+        highlights: &[Vec<usize>],
+        maybe_prompt: Option<String>
+    ) -> Option<usize>;
+
+    /// Lets the user pick any number of options, and returns their indexes
+    fn select_multiple(&self, options: &[String], maybe_prompt: Option<String>) -> Vec<usize>;
+
     fn confirm(self: &Self, prompt: String) -> bool;
 }
\ No newline at end of file
@@ -3,3 +3,6 @@ pub mod inputable;
 pub mod os_service;
 pub mod cmd_service;
 pub mod cmd_extension;
+pub mod ranking;
+
+pub mod history_writer;
\ No newline at end of file
@@ -1,5 +1,14 @@
-use crate::error::CmdError;
+use std::time::Duration;
+
+use crate::{ error::CmdError, models::execution_outcome::ExecutionOutcome };
 
 pub trait OSService {
-    fn execute_command(self: &Self, command: &str) -> Result<bool, CmdError>;
+    /// Runs the command and returns how it ended. Failing to start it at all is an error.
+    /// `recorded` is what goes to the shell history in its place, e.g. the command before its
+    /// variables were expanded.
+    fn execute_command(&self, command: &str, recorded: &str) -> Result<ExecutionOutcome, CmdError>;
+
+    /// Runs the command through `<shell> -c` and returns what it printed. The command is killed and an
+    /// error returned if it does not finish within `timeout`, or if it fails.
+    fn capture_output(&self, command: &str, timeout: Duration) -> Result<String, CmdError>;
 }
\ No newline at end of file
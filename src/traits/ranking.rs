@@ -0,0 +1,7 @@
+use crate::models::cmd_record::CmdRecord;
+
+/// Decides the order in which matching commands are offered.
+pub trait RankingStrategy {
+    /// Higher scores are offered first. `now` is in seconds since the UNIX epoch.
+    fn score(&self, record: &CmdRecord, now: i64) -> f64;
+}